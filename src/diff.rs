@@ -0,0 +1,395 @@
+use crate::model::{Dep, DepType, Store};
+use crate::store::{self, ItemChange};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+
+/// One field whose value differs, rendered for display.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ItemDiff {
+    pub id: String,
+    pub change: ItemChange,
+    pub title: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MilestoneDiff {
+    pub name: String,
+    pub change: ItemChange,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StoreDiff {
+    pub items: Vec<ItemDiff>,
+    pub deps_added: Vec<Dep>,
+    pub deps_removed: Vec<Dep>,
+    pub settings: Vec<FieldChange>,
+    pub milestones: Vec<MilestoneDiff>,
+}
+
+impl StoreDiff {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+            && self.deps_added.is_empty()
+            && self.deps_removed.is_empty()
+            && self.settings.is_empty()
+            && self.milestones.is_empty()
+    }
+}
+
+/// Field-level differences between two versions of a store. Fields are
+/// compared through their serialized form so new item fields show up
+/// without changes here.
+pub fn diff_stores(before: &Store, after: &Store) -> StoreDiff {
+    let items = store::changed_items(before, after)
+        .into_iter()
+        .map(|(id, change)| {
+            let old = before.items.get(&id);
+            let new = after.items.get(&id);
+            let title = new.or(old).map(|i| i.title.clone()).unwrap_or_default();
+            let fields = match change {
+                ItemChange::Modified => field_changes(&to_value(old), &to_value(new)),
+                _ => Vec::new(),
+            };
+            ItemDiff {
+                id,
+                change,
+                title,
+                fields,
+            }
+        })
+        .collect();
+
+    let old_deps: HashSet<&Dep> = before.deps().iter().collect();
+    let new_deps: HashSet<&Dep> = after.deps().iter().collect();
+    let mut deps_added: Vec<Dep> = new_deps
+        .difference(&old_deps)
+        .map(|d| (*d).clone())
+        .collect();
+    let mut deps_removed: Vec<Dep> = old_deps
+        .difference(&new_deps)
+        .map(|d| (*d).clone())
+        .collect();
+    for deps in [&mut deps_added, &mut deps_removed] {
+        deps.sort_by(|a, b| (&a.from_id, &a.to_id).cmp(&(&b.from_id, &b.to_id)));
+    }
+
+    let names: BTreeSet<&String> = before
+        .milestones
+        .keys()
+        .chain(after.milestones.keys())
+        .collect();
+    let milestones = names
+        .into_iter()
+        .filter_map(|name| {
+            let old = to_value(before.milestones.get(name));
+            let new = to_value(after.milestones.get(name));
+            let change = match (&old, &new) {
+                (Value::Null, _) => ItemChange::Added,
+                (_, Value::Null) => ItemChange::Removed,
+                _ if old != new => ItemChange::Modified,
+                _ => return None,
+            };
+            let fields = match change {
+                ItemChange::Modified => field_changes(&old, &new),
+                _ => Vec::new(),
+            };
+            Some(MilestoneDiff {
+                name: name.clone(),
+                change,
+                fields,
+            })
+        })
+        .collect();
+
+    StoreDiff {
+        items,
+        deps_added,
+        deps_removed,
+        settings: field_changes(
+            &to_value(Some(&before.settings)),
+            &to_value(Some(&after.settings)),
+        ),
+        milestones,
+    }
+}
+
+fn to_value<T: Serialize>(value: Option<&T>) -> Value {
+    value
+        .and_then(|v| serde_json::to_value(v).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn field_changes(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let old = before.as_object().unwrap_or(&empty);
+    let new = after.as_object().unwrap_or(&empty);
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|k| old.get(*k) != new.get(*k))
+        .map(|k| FieldChange {
+            field: k.clone(),
+            before: old.get(k).map(render),
+            after: new.get(k).map(render),
+        })
+        .collect()
+}
+
+/// Strings without their quotes; everything else as compact JSON.
+fn render(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn describe_dep(dep: &Dep) -> String {
+    match dep.dep_type {
+        DepType::Blocks => format!("{} blocks {}", dep.from_id, dep.to_id),
+        DepType::Parent => format!("{} child of {}", dep.from_id, dep.to_id),
+    }
+}
+
+fn sign(change: ItemChange) -> char {
+    match change {
+        ItemChange::Added => '+',
+        ItemChange::Removed => '-',
+        ItemChange::Modified => '~',
+    }
+}
+
+fn push_fields(out: &mut String, fields: &[FieldChange]) {
+    for field in fields {
+        out.push_str(&format!(
+            "    {}: {} -> {}\n",
+            field.field,
+            field.before.as_deref().unwrap_or("(none)"),
+            field.after.as_deref().unwrap_or("(none)")
+        ));
+    }
+}
+
+/// Human-readable diff, one line per change.
+pub fn format_diff(diff: &StoreDiff) -> String {
+    let mut out = String::new();
+    for item in &diff.items {
+        out.push_str(&format!(
+            "{} {} {}\n",
+            sign(item.change),
+            item.id,
+            item.title
+        ));
+        push_fields(&mut out, &item.fields);
+    }
+    for dep in &diff.deps_added {
+        out.push_str(&format!("+ dep {}\n", describe_dep(dep)));
+    }
+    for dep in &diff.deps_removed {
+        out.push_str(&format!("- dep {}\n", describe_dep(dep)));
+    }
+    for field in &diff.settings {
+        out.push_str(&format!(
+            "~ setting {}: {} -> {}\n",
+            field.field,
+            field.before.as_deref().unwrap_or("(default)"),
+            field.after.as_deref().unwrap_or("(default)")
+        ));
+    }
+    for milestone in &diff.milestones {
+        out.push_str(&format!(
+            "{} milestone {}\n",
+            sign(milestone.change),
+            milestone.name
+        ));
+        push_fields(&mut out, &milestone.fields);
+    }
+    out
+}
+
+/// Line-oriented rendering of a store for `git diff` textconv: one line per
+/// field, so git's line diff shows field-level changes.
+pub fn textconv(store: &Store) -> String {
+    let mut out = String::new();
+    for (id, item) in &store.items {
+        out.push_str(&format!("item {id}\n"));
+        if let Value::Object(fields) = to_value(Some(item)) {
+            for (key, value) in fields {
+                if key == "id" {
+                    continue;
+                }
+                match value {
+                    Value::Array(entries) => {
+                        for entry in entries {
+                            out.push_str(&format!("  {key}: {}\n", render(&entry)));
+                        }
+                    }
+                    other => out.push_str(&format!("  {key}: {}\n", render(&other))),
+                }
+            }
+        }
+    }
+    let mut deps: Vec<String> = store.deps().iter().map(describe_dep).collect();
+    deps.sort();
+    for dep in deps {
+        out.push_str(&format!("dep {dep}\n"));
+    }
+    if let Value::Object(fields) = to_value(Some(&store.settings)) {
+        for (key, value) in fields {
+            out.push_str(&format!("setting {key}: {}\n", render(&value)));
+        }
+    }
+    for (name, milestone) in &store.milestones {
+        out.push_str(&format!("milestone {name}\n"));
+        if let Value::Object(fields) = to_value(Some(milestone)) {
+            for (key, value) in fields {
+                if key != "name" {
+                    out.push_str(&format!("  {key}: {}\n", render(&value)));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Item;
+
+    fn store_with(ids: &[&str]) -> Store {
+        let mut s = Store::default();
+        for id in ids {
+            s.items.insert(
+                id.to_string(),
+                Item {
+                    title: format!("title {id}"),
+                    ..Item::for_test(id)
+                },
+            );
+        }
+        s
+    }
+
+    #[test]
+    fn field_level_changes() {
+        let before = store_with(&["lb-aaaa"]);
+        let mut after = before.clone();
+        let item = after.items.get_mut("lb-aaaa").unwrap();
+        item.priority = 0;
+        item.description = Some("details".to_string());
+
+        let diff = diff_stores(&before, &after);
+        assert_eq!(diff.items.len(), 1);
+        assert_eq!(diff.items[0].change, ItemChange::Modified);
+        assert_eq!(
+            diff.items[0].fields,
+            vec![
+                FieldChange {
+                    field: "description".to_string(),
+                    before: None,
+                    after: Some("details".to_string()),
+                },
+                FieldChange {
+                    field: "priority".to_string(),
+                    before: Some("2".to_string()),
+                    after: Some("0".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn added_removed_and_deps() {
+        let before = store_with(&["lb-aaaa", "lb-bbbb"]);
+        let mut after = before.clone();
+        after.items.remove("lb-bbbb");
+        after.items.extend(store_with(&["lb-cccc"]).items);
+        after.add_dep(Dep {
+            from_id: "lb-aaaa".to_string(),
+            to_id: "lb-cccc".to_string(),
+            dep_type: DepType::Blocks,
+            created_at: None,
+        });
+        after.settings.auto_close_parents = true;
+
+        let diff = diff_stores(&before, &after);
+        let changes: Vec<(&str, ItemChange)> = diff
+            .items
+            .iter()
+            .map(|i| (i.id.as_str(), i.change))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("lb-bbbb", ItemChange::Removed),
+                ("lb-cccc", ItemChange::Added)
+            ]
+        );
+        assert_eq!(diff.deps_added.len(), 1);
+        assert_eq!(diff.settings[0].field, "auto_close_parents");
+
+        let text = format_diff(&diff);
+        assert!(text.contains("- lb-bbbb title lb-bbbb"), "{text}");
+        assert!(text.contains("+ dep lb-aaaa blocks lb-cccc"), "{text}");
+        assert!(diff_stores(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn textconv_is_one_field_per_line() {
+        let mut s = store_with(&["lb-aaaa"]);
+        s.items.get_mut("lb-aaaa").unwrap().claimed_by = Some("alice".to_string());
+        let text = textconv(&s);
+        assert!(text.starts_with("item lb-aaaa\n"), "{text}");
+        assert!(text.contains("  title: title lb-aaaa\n"), "{text}");
+        assert!(text.contains("  claimed_by: alice\n"), "{text}");
+    }
+
+    #[test]
+    fn milestone_changes() {
+        let before = store_with(&[]);
+        let mut after = before.clone();
+        crate::milestone::create(&mut after, "v1", None).unwrap();
+        let diff = diff_stores(&before, &after);
+        assert_eq!(diff.milestones[0].change, ItemChange::Added);
+        assert!(format_diff(&diff).contains("+ milestone v1\n"));
+        let text = textconv(&after);
+        assert!(text.starts_with("milestone v1\n"), "{text}");
+        assert!(text.contains("  state: open\n"), "{text}");
+
+        let closed = {
+            let mut s = after.clone();
+            crate::milestone::close(&mut s, "v1").unwrap();
+            s
+        };
+        let diff = diff_stores(&after, &closed);
+        assert_eq!(
+            diff.milestones[0].fields,
+            vec![FieldChange {
+                field: "state".to_string(),
+                before: Some("open".to_string()),
+                after: Some("closed".to_string()),
+            }]
+        );
+        assert!(
+            format_diff(&diff).contains("~ milestone v1\n    state: open -> closed\n"),
+            "{}",
+            format_diff(&diff)
+        );
+        assert!(diff_stores(&closed, &closed).is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+//! Due dates: parsing what people type, and the deadlines items inherit
+//! from their parents.
+
+use crate::model::Store;
+use crate::store;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::HashSet;
+
+/// Today in local time, which is what "tomorrow" and "friday" mean.
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+/// Parse a due date relative to `today`: `2026-11-03`, `today`, `tomorrow`,
+/// `+3d`, `+2w`, or a weekday name for the next one after today.
+pub fn parse(s: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let s = s.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    let days = match s.as_str() {
+        "today" => Some(0),
+        "tomorrow" => Some(1),
+        _ => None,
+    };
+    let days = days.or_else(|| {
+        let rest = s.strip_prefix('+')?;
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (num, unit) = rest.split_at(split);
+        let n = num.parse::<i64>().ok()?;
+        match unit {
+            "d" => Some(n),
+            "w" => n.checked_mul(7),
+            _ => None,
+        }
+    });
+    let days = days.or_else(|| {
+        let day = s.parse::<Weekday>().ok()?;
+        let ahead = (day.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        Some(if ahead == 0 { 7 } else { i64::from(ahead) })
+    });
+    days.and_then(Duration::try_days)
+        .and_then(|d| today.checked_add_signed(d))
+        .ok_or_else(|| {
+            format!("invalid due date: {s} (e.g. 2026-11-03, tomorrow, +3d, +2w, friday)")
+        })
+}
+
+/// "in 3d", "today" or "2d overdue".
+pub fn describe(date: NaiveDate, today: NaiveDate) -> String {
+    let days = (date - today).num_days();
+    match days {
+        0 => "today".to_string(),
+        d if d > 0 => format!("in {d}d"),
+        d => format!("{}d overdue", -d),
+    }
+}
+
+/// The deadline an item works to: the earliest due date among it and its
+/// ancestors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deadline {
+    pub date: NaiveDate,
+    /// The ancestor it comes from, if not the item's own.
+    pub from: Option<String>,
+}
+
+pub fn deadline(store: &Store, id: &str) -> Option<Deadline> {
+    let mut best: Option<Deadline> = store
+        .items
+        .get(id)
+        .and_then(|i| i.due)
+        .map(|date| Deadline { date, from: None });
+    let mut seen = HashSet::from([id.to_string()]);
+    let mut cur = store::get_parent(store, id);
+    while let Some(pid) = cur {
+        if !seen.insert(pid.clone()) {
+            break;
+        }
+        if let Some(date) = store.items.get(&pid).and_then(|p| p.due)
+            && best.as_ref().is_none_or(|b| date < b.date)
+        {
+            best = Some(Deadline {
+                date,
+                from: Some(pid.clone()),
+            });
+        }
+        cur = store::get_parent(store, &pid);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Dep, DepType, Item};
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parses_absolute_and_relative_dates() {
+        // A Wednesday
+        let today = date("2026-10-14");
+        assert_eq!(parse("2026-11-03", today).unwrap(), date("2026-11-03"));
+        assert_eq!(parse("today", today).unwrap(), today);
+        assert_eq!(parse("Tomorrow", today).unwrap(), date("2026-10-15"));
+        assert_eq!(parse("+3d", today).unwrap(), date("2026-10-17"));
+        assert_eq!(parse("+2w", today).unwrap(), date("2026-10-28"));
+        assert_eq!(parse("friday", today).unwrap(), date("2026-10-16"));
+        assert_eq!(parse("mon", today).unwrap(), date("2026-10-19"));
+        assert_eq!(parse("wednesday", today).unwrap(), date("2026-10-21"));
+        assert!(parse("+3x", today).is_err());
+        assert!(parse("someday", today).is_err());
+        assert!(parse("+99999999d", today).is_err());
+        assert!(parse("+9999999999999d", today).is_err());
+        assert!(parse("+9999999999999999999w", today).is_err());
+        assert!(parse("+9999999999999999w", today).is_err());
+
+        assert_eq!(describe(date("2026-10-17"), today), "in 3d");
+        assert_eq!(describe(today, today), "today");
+        assert_eq!(describe(date("2026-10-12"), today), "2d overdue");
+    }
+
+    #[test]
+    fn deadlines_come_from_the_earliest_ancestor() {
+        let mut s = Store::default();
+        for (id, due) in [
+            ("lb-e", Some("2026-10-20")),
+            ("lb-f", Some("2026-10-30")),
+            ("lb-a", None),
+            ("lb-b", Some("2026-10-18")),
+        ] {
+            s.items.insert(
+                id.to_string(),
+                Item {
+                    due: due.map(date),
+                    ..Item::for_test(id)
+                },
+            );
+        }
+        for (from, to) in [("lb-f", "lb-e"), ("lb-a", "lb-f"), ("lb-b", "lb-f")] {
+            s.add_dep(Dep::new(from.into(), to.into(), DepType::Parent));
+        }
+        assert_eq!(
+            deadline(&s, "lb-a"),
+            Some(Deadline {
+                date: date("2026-10-20"),
+                from: Some("lb-e".to_string())
+            })
+        );
+        assert_eq!(deadline(&s, "lb-b").unwrap().from, None);
+        assert_eq!(deadline(&s, "lb-e").unwrap().date, date("2026-10-20"));
+        assert_eq!(deadline(&s, "lb-gone"), None);
+    }
+}
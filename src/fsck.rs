@@ -0,0 +1,374 @@
+//! Store integrity checks. Commands keep these invariants, but hand edits
+//! and merges of concurrent changes can break them:
+//!
+//! - every item is stored under its own ID
+//! - deps reference existing items, never the same item twice, and are unique
+//! - an item has at most one parent
+//! - neither parent nor blocking deps form a cycle
+//!
+//! [`repair`] fixes each violation the same way on every clone, so two
+//! agents repairing the same store produce the same result.
+
+use crate::diff::describe_dep;
+use crate::model::{Dep, DepType, Store};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The item map key differs from the item's own `id`.
+    MismatchedId { key: String, id: String },
+    /// A dep endpoint doesn't exist.
+    DanglingDep { dep: Dep, missing: String },
+    /// A dep from an item to itself.
+    SelfDep(Dep),
+    /// The same dep listed more than once.
+    DuplicateDep(Dep),
+    /// More than one parent dep on one item.
+    ExtraParents {
+        id: String,
+        kept: String,
+        dropped: Vec<String>,
+    },
+    /// A cycle of deps of one type, broken by dropping `dropped`.
+    Cycle {
+        dep_type: DepType,
+        ids: Vec<String>,
+        dropped: Dep,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MismatchedId { key, id } => write!(f, "item stored as {key} has id {id}"),
+            Problem::DanglingDep { dep, missing } => write!(
+                f,
+                "dep {} references missing item {missing}",
+                describe_dep(dep)
+            ),
+            Problem::SelfDep(dep) => write!(f, "dep {} points at itself", describe_dep(dep)),
+            Problem::DuplicateDep(dep) => write!(f, "duplicate dep {}", describe_dep(dep)),
+            Problem::ExtraParents { id, kept, dropped } => {
+                let mut parents = vec![kept.as_str()];
+                parents.extend(dropped.iter().map(String::as_str));
+                write!(
+                    f,
+                    "{id} has {} parents: {}",
+                    parents.len(),
+                    parents.join(", ")
+                )
+            }
+            Problem::Cycle { dep_type, ids, .. } => {
+                let kind = match dep_type {
+                    DepType::Blocks => "blocking",
+                    DepType::Parent => "parent",
+                };
+                write!(f, "{kind} cycle: {} -> {}", ids.join(" -> "), ids[0])
+            }
+        }
+    }
+}
+
+impl Problem {
+    /// What [`repair`] does about it.
+    pub fn fix(&self) -> String {
+        match self {
+            Problem::MismatchedId { key, .. } => format!("set id to {key}"),
+            Problem::DanglingDep { .. } | Problem::SelfDep(_) => "removed the dep".to_string(),
+            Problem::DuplicateDep(_) => "removed the duplicate".to_string(),
+            Problem::ExtraParents { kept, .. } => format!("kept parent {kept}"),
+            Problem::Cycle { dropped, .. } => format!("removed dep {}", describe_dep(dropped)),
+        }
+    }
+}
+
+/// Every invariant violation in the store, without changing it.
+pub fn check(store: &Store) -> Vec<Problem> {
+    repair(&mut store.clone())
+}
+
+/// Fix every violation in place, returning what was wrong:
+///
+/// - mismatched IDs take the map key, which is what deps refer to
+/// - dangling, self and duplicate deps are removed
+/// - extra parents and cycles as in [`resolve_structure`]
+pub fn repair(store: &mut Store) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (key, item) in store.items.iter_mut() {
+        if item.id != *key {
+            problems.push(Problem::MismatchedId {
+                key: key.clone(),
+                id: std::mem::replace(&mut item.id, key.clone()),
+            });
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let items = &store.items;
+    let mut deps = store.deps().to_vec();
+    deps.retain(|dep| {
+        let missing = [&dep.from_id, &dep.to_id]
+            .into_iter()
+            .find(|id| !items.contains_key(*id));
+        let problem = if let Some(missing) = missing {
+            Problem::DanglingDep {
+                dep: dep.clone(),
+                missing: missing.clone(),
+            }
+        } else if dep.from_id == dep.to_id {
+            Problem::SelfDep(dep.clone())
+        } else if !seen.insert(dep.clone()) {
+            Problem::DuplicateDep(dep.clone())
+        } else {
+            return true;
+        };
+        problems.push(problem);
+        false
+    });
+
+    problems.extend(resolve_structure(&mut deps));
+    store.set_deps(deps);
+    problems
+}
+
+/// Settle the conflicts that merging two consistent stores can create: an
+/// item with several parents keeps the most recently added one, and each
+/// cycle loses its most recently added edge. Deps without a timestamp count
+/// as oldest; ties keep the smallest parent ID and drop the greatest edge by
+/// (from, to) ID, so every clone settles them the same way.
+pub fn resolve_structure(deps: &mut Vec<Dep>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let mut parents: BTreeMap<&str, Vec<&Dep>> = BTreeMap::new();
+    for dep in deps.iter().filter(|d| d.dep_type == DepType::Parent) {
+        parents.entry(&dep.from_id).or_default().push(dep);
+    }
+    let mut dropped_parents: HashSet<Dep> = HashSet::new();
+    for (id, candidates) in parents {
+        if candidates.len() < 2 {
+            continue;
+        }
+        let Some(kept) = candidates.iter().max_by(|a, b| {
+            (a.created_at, Reverse(&a.to_id)).cmp(&(b.created_at, Reverse(&b.to_id)))
+        }) else {
+            continue;
+        };
+        let mut dropped: Vec<&Dep> = candidates.iter().copied().filter(|d| d != kept).collect();
+        dropped.sort_by(|a, b| a.to_id.cmp(&b.to_id));
+        dropped_parents.extend(dropped.iter().map(|d| (*d).clone()));
+        problems.push(Problem::ExtraParents {
+            id: id.to_string(),
+            kept: kept.to_id.clone(),
+            dropped: dropped.iter().map(|d| d.to_id.clone()).collect(),
+        });
+    }
+    deps.retain(|d| !dropped_parents.contains(d));
+
+    for dep_type in [DepType::Parent, DepType::Blocks] {
+        loop {
+            let edges = edges(deps, dep_type);
+            let Some(ids) = find_cycle(&edges) else {
+                break;
+            };
+            let by_ends: HashMap<(&str, &str), &Dep> = deps
+                .iter()
+                .filter(|d| d.dep_type == dep_type)
+                .map(|d| ((d.from_id.as_str(), d.to_id.as_str()), d))
+                .collect();
+            let dropped = (0..ids.len())
+                .filter_map(|i| {
+                    by_ends
+                        .get(&(ids[i].as_str(), ids[(i + 1) % ids.len()].as_str()))
+                        .copied()
+                })
+                .max_by(|a, b| {
+                    (a.created_at, &a.from_id, &a.to_id).cmp(&(b.created_at, &b.from_id, &b.to_id))
+                })
+                .cloned()
+                .expect("every cycle edge is a dep");
+            deps.retain(|d| *d != dropped);
+            problems.push(Problem::Cycle {
+                dep_type,
+                ids,
+                dropped,
+            });
+        }
+    }
+
+    problems
+}
+
+/// Outgoing edges of one dep type: child -> parent, blocker -> blocked.
+fn edges(deps: &[Dep], dep_type: DepType) -> BTreeMap<&str, BTreeSet<&str>> {
+    let mut edges: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for dep in deps.iter().filter(|d| d.dep_type == dep_type) {
+        edges.entry(&dep.from_id).or_default().insert(&dep.to_id);
+    }
+    edges
+}
+
+/// First cycle found by a depth-first search in ID order, as the IDs along
+/// it starting from where the search entered it.
+fn find_cycle(edges: &BTreeMap<&str, BTreeSet<&str>>) -> Option<Vec<String>> {
+    // false while on the current path, true once fully explored
+    let mut done: HashMap<&str, bool> = HashMap::new();
+    for &start in edges.keys() {
+        if done.contains_key(start) {
+            continue;
+        }
+        done.insert(start, false);
+        let mut path = vec![start];
+        let mut iters = vec![edges[start].iter()];
+        while let Some(iter) = iters.last_mut() {
+            match iter.next() {
+                Some(&next) => match done.get(next) {
+                    Some(false) => {
+                        let pos = path.iter().position(|id| *id == next)?;
+                        return Some(path[pos..].iter().map(|id| id.to_string()).collect());
+                    }
+                    Some(true) => {}
+                    None => {
+                        done.insert(next, false);
+                        path.push(next);
+                        iters.push(edges.get(next).map(|e| e.iter()).unwrap_or_default());
+                    }
+                },
+                None => {
+                    if let Some(id) = path.pop() {
+                        done.insert(id, true);
+                    }
+                    iters.pop();
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Item;
+    use chrono::Utc;
+
+    fn store_with(ids: &[&str]) -> Store {
+        let mut s = Store::default();
+        for id in ids {
+            s.items.insert(id.to_string(), Item::for_test(id));
+        }
+        s
+    }
+
+    fn dep(from: &str, to: &str, dep_type: DepType) -> Dep {
+        Dep {
+            from_id: from.to_string(),
+            to_id: to.to_string(),
+            dep_type,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn consistent_store_has_no_problems() {
+        let mut s = store_with(&["lb-a", "lb-b", "lb-c"]);
+        s.add_dep(dep("lb-a", "lb-b", DepType::Blocks));
+        s.add_dep(dep("lb-b", "lb-c", DepType::Blocks));
+        s.add_dep(dep("lb-a", "lb-c", DepType::Parent));
+        assert!(check(&s).is_empty());
+    }
+
+    #[test]
+    fn repairs_ids_and_bad_deps() {
+        let mut s = store_with(&["lb-a", "lb-b", "lb-c"]);
+        s.items.get_mut("lb-a").unwrap().id = "lb-x".to_string();
+        s.set_deps(vec![
+            dep("lb-a", "lb-gone", DepType::Blocks),
+            dep("lb-b", "lb-b", DepType::Blocks),
+            dep("lb-a", "lb-b", DepType::Blocks),
+            dep("lb-a", "lb-b", DepType::Blocks),
+            dep("lb-a", "lb-c", DepType::Parent),
+            dep("lb-a", "lb-b", DepType::Parent),
+        ]);
+        let problems = check(&s);
+        assert_eq!(problems.len(), 5, "{problems:?}");
+
+        let fixed = repair(&mut s);
+        assert_eq!(fixed, problems);
+        assert_eq!(s.items["lb-a"].id, "lb-a");
+        assert_eq!(
+            s.deps(),
+            vec![
+                dep("lb-a", "lb-b", DepType::Blocks),
+                dep("lb-a", "lb-b", DepType::Parent),
+            ]
+        );
+        assert_eq!(
+            fixed[4].to_string(),
+            "lb-a has 2 parents: lb-b, lb-c",
+            "{fixed:?}"
+        );
+        assert!(check(&s).is_empty());
+    }
+
+    #[test]
+    fn newest_parent_wins_and_newest_cycle_edge_goes() {
+        let at = |secs: i64| Some(Utc::now() + chrono::Duration::seconds(secs));
+        let mut deps = vec![
+            Dep {
+                created_at: at(2),
+                ..dep("lb-a", "lb-b", DepType::Parent)
+            },
+            Dep {
+                created_at: at(1),
+                ..dep("lb-a", "lb-c", DepType::Parent)
+            },
+            Dep {
+                created_at: at(1),
+                ..dep("lb-b", "lb-c", DepType::Blocks)
+            },
+            Dep {
+                created_at: at(0),
+                ..dep("lb-c", "lb-b", DepType::Blocks)
+            },
+        ];
+        let problems = resolve_structure(&mut deps);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert_eq!(problems[0].fix(), "kept parent lb-b");
+        assert_eq!(
+            problems[1].to_string(),
+            "blocking cycle: lb-b -> lb-c -> lb-b"
+        );
+        assert_eq!(problems[1].fix(), "removed dep lb-b blocks lb-c");
+        assert_eq!(
+            deps,
+            vec![
+                dep("lb-a", "lb-b", DepType::Parent),
+                dep("lb-c", "lb-b", DepType::Blocks),
+            ]
+        );
+    }
+
+    #[test]
+    fn breaks_every_cycle() {
+        let mut s = store_with(&["lb-a", "lb-b", "lb-c", "lb-d"]);
+        s.set_deps(vec![
+            dep("lb-a", "lb-b", DepType::Blocks),
+            dep("lb-b", "lb-c", DepType::Blocks),
+            dep("lb-c", "lb-a", DepType::Blocks),
+            dep("lb-c", "lb-d", DepType::Blocks),
+            dep("lb-d", "lb-c", DepType::Blocks),
+            dep("lb-a", "lb-b", DepType::Parent),
+            dep("lb-b", "lb-a", DepType::Parent),
+        ]);
+        let problems = repair(&mut s);
+        let cycles = problems
+            .iter()
+            .filter(|p| matches!(p, Problem::Cycle { .. }))
+            .count();
+        assert_eq!(cycles, 3, "{problems:?}");
+        assert!(check(&s).is_empty());
+    }
+}
@@ -14,6 +14,12 @@ fn run_git(args: &[&str]) -> Result<String, String> {
 }
 
 fn run_git_stdin(args: &[&str], stdin_data: &[u8]) -> Result<String, String> {
+    let stdout = run_git_stdin_raw(args, stdin_data)?;
+    Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+}
+
+/// Like `run_git_stdin`, but returns stdout untouched.
+fn run_git_stdin_raw(args: &[&str], stdin_data: &[u8]) -> Result<Vec<u8>, String> {
     use std::io::Write;
     let mut child = Command::new("git")
         .args(args)
@@ -32,107 +38,241 @@ fn run_git_stdin(args: &[&str], stdin_data: &[u8]) -> Result<String, String> {
         .wait_with_output()
         .map_err(|e| format!("failed to wait for git: {e}"))?;
     if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        Ok(output.stdout)
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
         Err(stderr)
     }
 }
 
-const BRANCH: &str = "litebrite";
-const STORE_FILENAME: &str = "store.json";
+pub const BRANCH: &str = "litebrite";
 
 pub fn branch_exists() -> bool {
     run_git(&["rev-parse", "--verify", &format!("refs/heads/{BRANCH}")]).is_ok()
 }
 
-pub fn has_remote() -> bool {
-    run_git(&["remote", "get-url", "origin"]).is_ok()
+pub fn has_remote(remote: &str) -> bool {
+    run_git(&["remote", "get-url", remote]).is_ok()
 }
 
-pub fn remote_branch_exists() -> bool {
-    run_git(&[
-        "rev-parse",
-        "--verify",
-        &format!("refs/remotes/origin/{BRANCH}"),
-    ])
-    .is_ok()
+/// Tracking ref for the litebrite branch on `remote`.
+pub fn tracking_ref(remote: &str) -> String {
+    format!("refs/remotes/{remote}/{BRANCH}")
 }
 
-pub fn init_branch(store_json: &str) -> Result<(), String> {
+pub fn remote_branch_exists(remote: &str) -> bool {
+    run_git(&["rev-parse", "--verify", &tracking_ref(remote)]).is_ok()
+}
+
+/// Create the branch with `tree` as its first commit, or track the remote
+/// branch if `remote` already has one.
+pub fn init_branch(tree: &str, remote: &str) -> Result<(), String> {
     if branch_exists() {
         return Err("litebrite already initialized".to_string());
     }
 
     // Check if remote has the branch — if so, set up tracking instead
     // Try fetching first to see if remote exists
-    if fetch().is_ok() && remote_branch_exists() {
-        run_git(&["branch", BRANCH, &format!("refs/remotes/origin/{BRANCH}")])?;
+    if fetch(remote).is_ok() && remote_branch_exists(remote) {
+        run_git(&["branch", BRANCH, &tracking_ref(remote)])?;
         return Ok(());
     }
 
     // Create orphan branch with empty store
-    let blob_hash = run_git_stdin(&["hash-object", "-w", "--stdin"], store_json.as_bytes())?;
-
-    let tree_entry = format!("100644 blob {blob_hash}\t{STORE_FILENAME}\n");
-    let tree_hash = run_git_stdin(&["mktree"], tree_entry.as_bytes())?;
-
-    let commit_hash = run_git(&["commit-tree", &tree_hash, "-m", "Initialize litebrite"])?;
+    let commit_hash = run_git(&["commit-tree", tree, "-m", "Initialize litebrite"])?;
 
     run_git(&["update-ref", &format!("refs/heads/{BRANCH}"), &commit_hash])?;
 
     // Push to remote if one is configured
-    if has_remote() {
-        push()?;
+    if has_remote(remote) {
+        push(remote)?;
     }
 
     Ok(())
 }
 
-pub fn read_store() -> Result<String, String> {
-    run_git(&["show", &format!("{BRANCH}:{STORE_FILENAME}")])
+/// Commit `tree` on top of the branch.
+pub fn commit_tree(tree: &str, message: &str) -> Result<(), String> {
+    let parent = run_git(&["rev-parse", &format!("refs/heads/{BRANCH}")])?;
+
+    let commit_hash = run_git(&["commit-tree", tree, "-p", &parent, "-m", message])?;
+
+    run_git(&["update-ref", &format!("refs/heads/{BRANCH}"), &commit_hash])?;
+
+    Ok(())
 }
 
-pub fn read_store_from_ref(git_ref: &str) -> Result<String, String> {
-    run_git(&["show", &format!("{git_ref}:{STORE_FILENAME}")])
+/// One line of `git ls-tree` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub mode: String,
+    pub kind: String,
+    pub id: String,
+    pub path: String,
 }
 
-pub fn write_store(store_json: &str, message: &str) -> Result<(), String> {
-    let parent = run_git(&["rev-parse", &format!("refs/heads/{BRANCH}")])?;
+impl TreeEntry {
+    pub fn blob(id: String, path: String) -> TreeEntry {
+        TreeEntry {
+            mode: "100644".to_string(),
+            kind: "blob".to_string(),
+            id,
+            path,
+        }
+    }
+}
+
+/// Entries of a tree (or a commit's tree); with `recursive`, blobs in
+/// subdirectories are listed with their full path.
+pub fn ls_tree(treeish: &str, recursive: bool) -> Result<Vec<TreeEntry>, String> {
+    let mut args = vec!["ls-tree"];
+    if recursive {
+        args.push("-r");
+    }
+    args.push(treeish);
+    let listing = run_git(&args)?;
+    Ok(listing
+        .lines()
+        .filter_map(|line| {
+            let (meta, path) = line.split_once('\t')?;
+            let mut parts = meta.split_whitespace();
+            Some(TreeEntry {
+                mode: parts.next()?.to_string(),
+                kind: parts.next()?.to_string(),
+                id: parts.next()?.to_string(),
+                path: path.to_string(),
+            })
+        })
+        .collect())
+}
 
-    let blob_hash = run_git_stdin(&["hash-object", "-w", "--stdin"], store_json.as_bytes())?;
+/// Build a tree from entries whose paths are plain names (no slashes).
+pub fn mktree(entries: &[TreeEntry]) -> Result<String, String> {
+    let input: String = entries
+        .iter()
+        .map(|e| format!("{} {} {}\t{}\n", e.mode, e.kind, e.id, e.path))
+        .collect();
+    run_git_stdin(&["mktree"], input.as_bytes())
+}
 
-    let tree_entry = format!("100644 blob {blob_hash}\t{STORE_FILENAME}\n");
-    let tree_hash = run_git_stdin(&["mktree"], tree_entry.as_bytes())?;
+/// Write `content` to the object database and return its blob ID.
+pub fn hash_blob(content: &str) -> Result<String, String> {
+    run_git_stdin(&["hash-object", "-w", "--stdin"], content.as_bytes())
+}
 
-    let commit_hash = run_git(&["commit-tree", &tree_hash, "-p", &parent, "-m", message])?;
+/// Write many blobs with a single `hash-object` call; IDs come back in order.
+pub fn hash_blobs(contents: &[&str]) -> Result<Vec<String>, String> {
+    if contents.len() < 2 {
+        return contents.iter().map(|c| hash_blob(c)).collect();
+    }
+    let dir = common_dir()?.join(format!("litebrite-tmp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    let result = (|| {
+        let mut paths = String::new();
+        for (i, content) in contents.iter().enumerate() {
+            let path = dir.join(i.to_string());
+            std::fs::write(&path, content).map_err(|e| format!("write {}: {e}", path.display()))?;
+            paths.push_str(&format!("{}\n", path.display()));
+        }
+        run_git_stdin(
+            &["hash-object", "-w", "--no-filters", "--stdin-paths"],
+            paths.as_bytes(),
+        )
+    })();
+    let _ = std::fs::remove_dir_all(&dir);
+    let ids: Vec<String> = result?.lines().map(String::from).collect();
+    if ids.len() != contents.len() {
+        return Err(format!(
+            "hash-object returned {} IDs for {} blobs",
+            ids.len(),
+            contents.len()
+        ));
+    }
+    Ok(ids)
+}
+
+/// Contents of many blobs in one `git cat-file --batch` call, in order.
+pub fn cat_blobs(ids: &[&str]) -> Result<Vec<String>, String> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let input: String = ids.iter().map(|id| format!("{id}\n")).collect();
+    let out = run_git_stdin_raw(&["cat-file", "--batch"], input.as_bytes())?;
+    let mut blobs = Vec::with_capacity(ids.len());
+    let mut rest = out.as_slice();
+    for id in ids {
+        let header_end = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| format!("truncated cat-file output at {id}"))?;
+        let header = String::from_utf8_lossy(&rest[..header_end]).to_string();
+        let size: usize = match header.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [_, "blob", size] => size.parse().map_err(|_| format!("bad header: {header}"))?,
+            _ => return Err(format!("cannot read blob {id}: {header}")),
+        };
+        let body = rest
+            .get(header_end + 1..header_end + 1 + size)
+            .ok_or_else(|| format!("truncated cat-file output at {id}"))?;
+        blobs.push(String::from_utf8_lossy(body).to_string());
+        rest = rest.get(header_end + 2 + size..).unwrap_or_default();
+    }
+    Ok(blobs)
+}
+
+/// Contents of `<treeish>:<path>`.
+pub fn show_file(treeish: &str, path: &str) -> Result<String, String> {
+    run_git(&["show", &format!("{treeish}:{path}")])
+}
 
+/// Tree ID of a commit.
+pub fn tree_of(commit: &str) -> Result<String, String> {
+    run_git(&["rev-parse", &format!("{commit}^{{tree}}")])
+}
+
+/// Read a file other than the store from the tip of the branch.
+pub fn read_branch_file(path: &str) -> Option<String> {
+    show_file(BRANCH, path).ok()
+}
+
+/// Commit `content` as `path` on the branch next to the store.
+pub fn write_branch_file(path: &str, content: &str, message: &str) -> Result<(), String> {
+    let parent = run_git(&["rev-parse", &format!("refs/heads/{BRANCH}")])?;
+    let mut entries = ls_tree(&parent, false)?;
+    entries.retain(|e| e.path != path);
+    entries.push(TreeEntry::blob(hash_blob(content)?, path.to_string()));
+    let tree_hash = mktree(&entries)?;
+    let commit_hash = run_git(&["commit-tree", &tree_hash, "-p", &parent, "-m", message])?;
     run_git(&["update-ref", &format!("refs/heads/{BRANCH}"), &commit_hash])?;
+    Ok(())
+}
 
+/// Set a git config value in the repository's config.
+pub fn config_set(key: &str, value: &str) -> Result<(), String> {
+    run_git(&["config", key, value])?;
     Ok(())
 }
 
-pub fn fetch() -> Result<(), String> {
+pub fn fetch(remote: &str) -> Result<(), String> {
     run_git(&[
         "fetch",
-        "origin",
-        &format!("{BRANCH}:refs/remotes/origin/{BRANCH}"),
+        remote,
+        &format!("{BRANCH}:{}", tracking_ref(remote)),
     ])?;
     Ok(())
 }
 
-pub fn push() -> Result<(), String> {
-    run_git(&["push", "origin", BRANCH])?;
+pub fn push(remote: &str) -> Result<(), String> {
+    run_git(&["push", remote, BRANCH])?;
     Ok(())
 }
 
-pub fn fast_forward() -> Result<(), String> {
-    if !remote_branch_exists() {
+pub fn fast_forward(remote: &str) -> Result<(), String> {
+    if !remote_branch_exists(remote) {
         return Ok(());
     }
 
     let local = run_git(&["rev-parse", &format!("refs/heads/{BRANCH}")])?;
-    let remote = run_git(&["rev-parse", &format!("refs/remotes/origin/{BRANCH}")])?;
+    let remote = run_git(&["rev-parse", &tracking_ref(remote)])?;
 
     if local == remote {
         return Ok(());
@@ -150,12 +290,12 @@ pub fn fast_forward() -> Result<(), String> {
     Ok(())
 }
 
-pub fn merge_base() -> Result<Option<String>, String> {
-    if !remote_branch_exists() {
+pub fn merge_base(remote: &str) -> Result<Option<String>, String> {
+    if !remote_branch_exists(remote) {
         return Ok(None);
     }
     let local = run_git(&["rev-parse", &format!("refs/heads/{BRANCH}")])?;
-    let remote = run_git(&["rev-parse", &format!("refs/remotes/origin/{BRANCH}")])?;
+    let remote = run_git(&["rev-parse", &tracking_ref(remote)])?;
     match run_git(&["merge-base", &local, &remote]) {
         Ok(base) => Ok(Some(base)),
         Err(_) => Ok(None), // no common ancestor
@@ -163,19 +303,14 @@ pub fn merge_base() -> Result<Option<String>, String> {
 }
 
 pub fn create_merge_commit(
-    store_json: &str,
+    tree: &str,
     parent1: &str,
     parent2: &str,
     message: &str,
 ) -> Result<(), String> {
-    let blob_hash = run_git_stdin(&["hash-object", "-w", "--stdin"], store_json.as_bytes())?;
-
-    let tree_entry = format!("100644 blob {blob_hash}\t{STORE_FILENAME}\n");
-    let tree_hash = run_git_stdin(&["mktree"], tree_entry.as_bytes())?;
-
     let commit_hash = run_git(&[
         "commit-tree",
-        &tree_hash,
+        tree,
         "-p",
         parent1,
         "-p",
@@ -189,14 +324,49 @@ pub fn create_merge_commit(
     Ok(())
 }
 
-pub fn git_user_name() -> Result<String, String> {
-    run_git(&["config", "user.name"])
+/// Read a git config value; `None` if unset or empty.
+pub fn config_get(key: &str) -> Option<String> {
+    run_git(&["config", key]).ok().filter(|v| !v.is_empty())
+}
+
+/// Read every value of a multi-valued git config key, in order.
+pub fn config_get_all(key: &str) -> Vec<String> {
+    run_git(&["config", "--get-all", key])
+        .map(|out| {
+            out.lines()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The repository's shared git directory (the same for all worktrees).
+pub fn common_dir() -> Result<std::path::PathBuf, String> {
+    run_git(&["rev-parse", "--git-common-dir"]).map(std::path::PathBuf::from)
 }
 
 pub fn local_ref() -> Result<String, String> {
     run_git(&["rev-parse", &format!("refs/heads/{BRANCH}")])
 }
 
-pub fn remote_ref() -> Result<String, String> {
-    run_git(&["rev-parse", &format!("refs/remotes/origin/{BRANCH}")])
+/// Commits on the local branch missing from `remote`'s tracking ref, and
+/// commits there missing locally, as of the last fetch.
+pub fn ahead_behind(remote: &str) -> Result<(u32, u32), String> {
+    let out = run_git(&[
+        "rev-list",
+        "--left-right",
+        "--count",
+        &format!("refs/heads/{BRANCH}...{}", tracking_ref(remote)),
+    ])?;
+    let mut counts = out.split_whitespace().map(|n| n.parse::<u32>());
+    match (counts.next(), counts.next()) {
+        (Some(Ok(ahead)), Some(Ok(behind))) => Ok((ahead, behind)),
+        _ => Err(format!("unexpected rev-list output: {out}")),
+    }
+}
+
+pub fn remote_ref(remote: &str) -> Result<String, String> {
+    run_git(&["rev-parse", &tracking_ref(remote)])
 }
@@ -0,0 +1,274 @@
+//! Rendering the dependency graph as Graphviz DOT or a Mermaid flowchart.
+
+use crate::model::{DepType, Item, ItemType, Status, Store};
+use crate::store;
+use chrono::Utc;
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Dot => write!(f, "dot"),
+            Format::Mermaid => write!(f, "mermaid"),
+        }
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(Format::Dot),
+            "mermaid" => Ok(Format::Mermaid),
+            _ => Err(format!("unknown graph format: {s} (valid: dot, mermaid)")),
+        }
+    }
+}
+
+/// Items to draw: everything, or `root` and its descendants plus whatever
+/// blocks them or is blocked by them, so outside blockers still show. Only
+/// items passing `keep` are included.
+pub fn select<'a>(
+    store: &'a Store,
+    root: Option<&str>,
+    keep: impl Fn(&Item) -> bool,
+) -> Vec<&'a Item> {
+    let ids: BTreeSet<String> = match root {
+        None => store.items.keys().cloned().collect(),
+        Some(root) => {
+            let mut subtree = BTreeSet::new();
+            let mut stack = vec![root.to_string()];
+            while let Some(id) = stack.pop() {
+                if subtree.insert(id.clone()) {
+                    stack.extend(store::get_children(store, &id));
+                }
+            }
+            let mut ids = subtree.clone();
+            for id in &subtree {
+                ids.extend(store::get_blockers(store, id));
+                ids.extend(store::get_blocking(store, id));
+            }
+            ids
+        }
+    };
+    ids.iter()
+        .filter_map(|id| store.items.get(id))
+        .filter(|item| keep(item))
+        .collect()
+}
+
+pub fn render(store: &Store, items: &[&Item], format: Format) -> String {
+    match format {
+        Format::Dot => render_dot(store, items),
+        Format::Mermaid => render_mermaid(store, items),
+    }
+}
+
+/// Deps between drawn items, in (from, to) order.
+fn edges<'a>(store: &'a Store, items: &[&Item]) -> Vec<(&'a str, &'a str, DepType)> {
+    let ids: BTreeSet<&str> = items.iter().map(|i| i.id.as_str()).collect();
+    let mut edges: Vec<(&str, &str, DepType)> = store
+        .deps()
+        .iter()
+        .filter(|d| ids.contains(d.from_id.as_str()) && ids.contains(d.to_id.as_str()))
+        .map(|d| (d.from_id.as_str(), d.to_id.as_str(), d.dep_type))
+        .collect();
+    edges.sort_by_key(|(from, to, _)| (*from, *to));
+    edges.dedup();
+    edges
+}
+
+/// Node label: ID, title and whoever holds an active claim.
+fn label(item: &Item) -> String {
+    let mut label = format!("{}: {}", item.id, item.title);
+    if let Some(who) = store::active_claimant(item, Utc::now()) {
+        label.push_str(&format!(" (@{who})"));
+    }
+    label
+}
+
+fn is_claimed(item: &Item) -> bool {
+    item.status == Status::Open && store::active_claimant(item, Utc::now()).is_some()
+}
+
+fn render_dot(store: &Store, items: &[&Item]) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut out =
+        String::from("digraph litebrite {\n  rankdir=LR;\n  node [fontname=\"Helvetica\"];\n");
+    for item in items {
+        let shape = match item.item_type {
+            ItemType::Epic => "box3d",
+            ItemType::Feature => "box",
+            ItemType::Task => "ellipse",
+        };
+        let style = if item.status == Status::Closed {
+            ", style=dashed, color=gray, fontcolor=gray"
+        } else if is_claimed(item) {
+            ", style=filled, fillcolor=lightyellow"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "  {} [label={}, shape={shape}{style}];\n",
+            quote(&item.id),
+            quote(&label(item))
+        ));
+    }
+    for (from, to, dep_type) in edges(store, items) {
+        let style = match dep_type {
+            DepType::Blocks => "label=\"blocks\"",
+            DepType::Parent => "label=\"child of\", style=dashed, arrowhead=empty",
+        };
+        out.push_str(&format!("  {} -> {} [{style}];\n", quote(from), quote(to)));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(store: &Store, items: &[&Item]) -> String {
+    // Mermaid node IDs can't contain '-'; labels can't contain raw quotes
+    let node = |id: &str| id.replace('-', "_");
+    let text = |s: &str| s.replace('"', "#quot;");
+    let mut out = String::from("flowchart LR\n");
+    let mut closed = Vec::new();
+    let mut claimed = Vec::new();
+    for item in items {
+        let label = text(&label(item));
+        let shape = match item.item_type {
+            ItemType::Epic => format!("[[\"{label}\"]]"),
+            ItemType::Feature => format!("[\"{label}\"]"),
+            ItemType::Task => format!("(\"{label}\")"),
+        };
+        out.push_str(&format!("  {}{shape}\n", node(&item.id)));
+        if item.status == Status::Closed {
+            closed.push(node(&item.id));
+        } else if is_claimed(item) {
+            claimed.push(node(&item.id));
+        }
+    }
+    for (from, to, dep_type) in edges(store, items) {
+        let arrow = match dep_type {
+            DepType::Blocks => "-->|blocks|",
+            DepType::Parent => "-.->|child of|",
+        };
+        out.push_str(&format!("  {} {arrow} {}\n", node(from), node(to)));
+    }
+    out.push_str("  classDef closed stroke-dasharray: 5 5,color:#888\n");
+    out.push_str("  classDef claimed fill:#ffffcc\n");
+    for (class, ids) in [("closed", closed), ("claimed", claimed)] {
+        if !ids.is_empty() {
+            out.push_str(&format!("  class {} {class}\n", ids.join(",")));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Dep;
+
+    fn item(id: &str, title: &str, item_type: ItemType) -> Item {
+        Item {
+            title: title.to_string(),
+            item_type,
+            ..Item::for_test(id)
+        }
+    }
+
+    /// Epic lb-e with children lb-a and lb-b; lb-x (outside) blocks lb-a,
+    /// which blocks lb-b; lb-y is unrelated.
+    fn sample() -> Store {
+        let mut s = Store::default();
+        for (id, item_type) in [
+            ("lb-e", ItemType::Epic),
+            ("lb-a", ItemType::Task),
+            ("lb-b", ItemType::Feature),
+            ("lb-x", ItemType::Task),
+            ("lb-y", ItemType::Task),
+        ] {
+            s.items
+                .insert(id.to_string(), item(id, &format!("do \"{id}\""), item_type));
+        }
+        s.items.get_mut("lb-b").unwrap().status = Status::Closed;
+        s.items.get_mut("lb-a").unwrap().claimed_by = Some("alice".to_string());
+        for (from, to, dep_type) in [
+            ("lb-a", "lb-e", DepType::Parent),
+            ("lb-b", "lb-e", DepType::Parent),
+            ("lb-x", "lb-a", DepType::Blocks),
+            ("lb-a", "lb-b", DepType::Blocks),
+        ] {
+            s.add_dep(Dep::new(from.to_string(), to.to_string(), dep_type));
+        }
+        s
+    }
+
+    fn ids(items: &[&Item]) -> Vec<String> {
+        items.iter().map(|i| i.id.clone()).collect()
+    }
+
+    #[test]
+    fn select_subtree_with_outside_blockers() {
+        let s = sample();
+        assert_eq!(ids(&select(&s, None, |_| true)).len(), 5);
+        assert_eq!(
+            ids(&select(&s, Some("lb-e"), |_| true)),
+            ["lb-a", "lb-b", "lb-e", "lb-x"]
+        );
+        assert_eq!(
+            ids(&select(&s, Some("lb-e"), |i| i.status == Status::Open)),
+            ["lb-a", "lb-e", "lb-x"]
+        );
+    }
+
+    #[test]
+    fn dot_output() {
+        let s = sample();
+        let dot = render(&s, &select(&s, Some("lb-e"), |_| true), Format::Dot);
+        assert!(dot.starts_with("digraph litebrite {"), "{dot}");
+        assert!(
+            dot.contains(r#""lb-e" [label="lb-e: do \"lb-e\"", shape=box3d];"#),
+            "{dot}"
+        );
+        assert!(
+            dot.contains("(@alice)\", shape=ellipse, style=filled"),
+            "{dot}"
+        );
+        assert!(dot.contains("shape=box, style=dashed"), "{dot}");
+        assert!(
+            dot.contains(r#""lb-x" -> "lb-a" [label="blocks"];"#),
+            "{dot}"
+        );
+        assert!(
+            dot.contains(r#""lb-a" -> "lb-e" [label="child of""#),
+            "{dot}"
+        );
+        assert!(!dot.contains("lb-y"), "{dot}");
+    }
+
+    #[test]
+    fn mermaid_output() {
+        let s = sample();
+        let mermaid = render(&s, &select(&s, None, |_| true), Format::Mermaid);
+        assert!(mermaid.starts_with("flowchart LR\n"), "{mermaid}");
+        assert!(
+            mermaid.contains("  lb_e[[\"lb-e: do #quot;lb-e#quot;\"]]\n"),
+            "{mermaid}"
+        );
+        assert!(mermaid.contains("  lb_x -->|blocks| lb_a\n"), "{mermaid}");
+        assert!(
+            mermaid.contains("  lb_b -.->|child of| lb_e\n"),
+            "{mermaid}"
+        );
+        assert!(mermaid.contains("  class lb_b closed\n"), "{mermaid}");
+        assert!(mermaid.contains("  class lb_a claimed\n"), "{mermaid}");
+    }
+}
@@ -0,0 +1,515 @@
+//! How a [`Store`] maps onto the branch's git tree.
+//!
+//! The single-file layout keeps everything in `store.json`. The per-item
+//! layout keeps one `items/<id>.json` per item plus `deps.json` and
+//! `meta.json` (settings), so a commit only adds blobs for the items it
+//! touched and merges only parse items changed on both sides. Reads detect
+//! the layout from the tree; writes use `settings.layout`, so switching the
+//! setting migrates the branch on the next write.
+
+use crate::fsck::Problem;
+use crate::git::{self, TreeEntry};
+use crate::migrate;
+use crate::model::{Dep, Item, Layout, SCHEMA_VERSION, Settings, Store};
+use crate::store::{self, ClaimAuthority};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{LazyLock, Mutex};
+
+const STORE_FILE: &str = "store.json";
+const ITEMS_DIR: &str = "items";
+const DEPS_FILE: &str = "deps.json";
+const META_FILE: &str = "meta.json";
+
+/// Blob IDs of contents already read from or written to the object database
+/// in this process, so rewriting the store only hashes files that changed.
+static KNOWN_BLOBS: LazyLock<Mutex<HashMap<String, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn remember(content: &str, id: &str) {
+    if let Ok(mut known) = KNOWN_BLOBS.lock() {
+        known.insert(content.to_string(), id.to_string());
+    }
+}
+
+/// Blob IDs for `contents`, hashing only those not seen before.
+fn blobs_for(contents: &[String]) -> Result<Vec<String>, String> {
+    let known = KNOWN_BLOBS
+        .lock()
+        .map(|k| contents.iter().map(|c| k.get(c).cloned()).collect())
+        .unwrap_or_else(|_| vec![None; contents.len()]);
+    let missing: Vec<&str> = contents
+        .iter()
+        .zip(&known)
+        .filter(|(_, id)| id.is_none())
+        .map(|(c, _)| c.as_str())
+        .collect();
+    let mut hashed = git::hash_blobs(&missing)?.into_iter();
+    let mut ids = Vec::with_capacity(contents.len());
+    for (content, id) in contents.iter().zip(known) {
+        let id = match id {
+            Some(id) => id,
+            None => {
+                let id = hashed.next().ok_or("missing blob ID from hash-object")?;
+                remember(content, &id);
+                id
+            }
+        };
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+fn item_json(item: &Item) -> Result<String, String> {
+    serde_json::to_string_pretty(item).map_err(|e| format!("failed to serialize item: {e}"))
+}
+
+/// Item ID for a path inside `items/`.
+fn item_id(path: &str) -> Option<&str> {
+    path.strip_prefix(ITEMS_DIR)?
+        .strip_prefix('/')?
+        .strip_suffix(".json")
+}
+
+fn is_single_file(entries: &[TreeEntry]) -> bool {
+    entries.iter().any(|e| e.path == STORE_FILE)
+}
+
+/// Read the store at any commit, in either layout.
+pub fn load(treeish: &str) -> Result<Store, String> {
+    store::from_value(load_value(treeish)?)
+}
+
+/// Raw JSON of the store at any commit, before migration. The per-item
+/// layout is assembled into the same shape as `store.json`.
+pub fn load_value(treeish: &str) -> Result<Value, String> {
+    let entries = git::ls_tree(treeish, true)?;
+    if is_single_file(&entries) {
+        let json = git::show_file(treeish, STORE_FILE)?;
+        return serde_json::from_str(&json).map_err(|e| format!("invalid store JSON: {e}"));
+    }
+    if !entries.iter().any(|e| e.path == META_FILE) {
+        return Err(format!("no litebrite store at {treeish}"));
+    }
+
+    let wanted: Vec<&TreeEntry> = entries
+        .iter()
+        .filter(|e| e.path == DEPS_FILE || e.path == META_FILE || item_id(&e.path).is_some())
+        .collect();
+    let ids: Vec<&str> = wanted.iter().map(|e| e.id.as_str()).collect();
+    let contents = git::cat_blobs(&ids)?;
+
+    let mut meta = Map::new();
+    let mut items = Map::new();
+    let mut deps = Value::Array(Vec::new());
+    for (entry, content) in wanted.into_iter().zip(contents) {
+        remember(&content, &entry.id);
+        let value: Value = parse(&entry.path, &content)?;
+        if entry.path == DEPS_FILE {
+            deps = value;
+        } else if entry.path == META_FILE {
+            meta = meta_object(value)?;
+        } else if let Some(id) = item_id(&entry.path) {
+            items.insert(id.to_string(), value);
+        }
+    }
+    meta.insert("items".to_string(), Value::Object(items));
+    meta.insert("deps".to_string(), deps);
+    Ok(Value::Object(meta))
+}
+
+/// `meta.json` holds the store minus items and deps. Before schema
+/// versioning it held just the settings.
+fn meta_object(value: Value) -> Result<Map<String, Value>, String> {
+    match value {
+        Value::Object(map) if map.contains_key("schema_version") => Ok(map),
+        Value::Object(settings) => Ok(Map::from_iter([(
+            "settings".to_string(),
+            Value::Object(settings),
+        )])),
+        _ => Err(format!("invalid {META_FILE}: expected an object")),
+    }
+}
+
+/// A store with no items or deps holding what `meta.json` says, in either
+/// of its shapes.
+fn meta_store(name: &str, content: &str) -> Result<Store, String> {
+    let mut meta = meta_object(parse(name, content)?)?;
+    meta.insert("items".to_string(), Value::Object(Map::new()));
+    meta.insert("deps".to_string(), Value::Array(Vec::new()));
+    serde_json::from_value(Value::Object(meta)).map_err(|e| format!("invalid {name}: {e}"))
+}
+
+fn meta_json(s: &Store) -> Result<String, String> {
+    let mut meta = Map::new();
+    meta.insert("schema_version".to_string(), Value::from(s.schema_version));
+    if !s.settings.is_default() {
+        let settings = serde_json::to_value(&s.settings).map_err(|e| e.to_string())?;
+        meta.insert("settings".to_string(), settings);
+    }
+    if !s.milestones.is_empty() {
+        let milestones = serde_json::to_value(&s.milestones).map_err(|e| e.to_string())?;
+        meta.insert("milestones".to_string(), milestones);
+    }
+    for (key, value) in &s.extra {
+        meta.insert(key.clone(), value.clone());
+    }
+    serde_json::to_string_pretty(&meta).map_err(|e| e.to_string())
+}
+
+fn parse<T: serde::de::DeserializeOwned>(path: &str, content: &str) -> Result<T, String> {
+    serde_json::from_str(content).map_err(|e| format!("invalid {path}: {e}"))
+}
+
+/// Commit the store on top of the branch.
+pub fn save(s: &Store, message: &str) -> Result<(), String> {
+    let parent = git::local_ref()?;
+    let tree = build_tree(s, &[&parent])?;
+    git::commit_tree(&tree, message)
+}
+
+/// Tree for `s` in its configured layout. Files that aren't part of the
+/// store (such as `.gitattributes`) are carried over from `parents`, the
+/// first parent's copy winning.
+pub fn build_tree(s: &Store, parents: &[&str]) -> Result<String, String> {
+    migrate::check_writable(s.schema_version)?;
+    let mut entries = match s.settings.layout {
+        Layout::SingleFile => {
+            let ids = blobs_for(&[store::to_json(s)?])?;
+            vec![TreeEntry::blob(ids[0].clone(), STORE_FILE.to_string())]
+        }
+        Layout::PerItem => {
+            let contents: Vec<String> =
+                s.items.values().map(item_json).collect::<Result<_, _>>()?;
+            let blobs = blobs_for(&contents)?;
+            let items = s.items.keys().cloned().zip(blobs).collect();
+            per_item_entries(items, s)?
+        }
+    };
+    add_extra_entries(&mut entries, parents)?;
+    git::mktree(&entries)
+}
+
+/// Top-level entries of a per-item tree from item blob IDs and the rest of
+/// the store (whose own `items` are ignored).
+fn per_item_entries(
+    items: BTreeMap<String, String>,
+    rest: &Store,
+) -> Result<Vec<TreeEntry>, String> {
+    let mut deps = rest.deps().to_vec();
+    deps.sort_by(|a, b| (&a.from_id, &a.to_id).cmp(&(&b.from_id, &b.to_id)));
+    let files = [
+        serde_json::to_string_pretty(&deps).map_err(|e| e.to_string())?,
+        meta_json(rest)?,
+    ];
+    let blobs = blobs_for(&files)?;
+    let mut entries = vec![
+        TreeEntry::blob(blobs[0].clone(), DEPS_FILE.to_string()),
+        TreeEntry::blob(blobs[1].clone(), META_FILE.to_string()),
+    ];
+    if !items.is_empty() {
+        let item_entries: Vec<TreeEntry> = items
+            .into_iter()
+            .map(|(id, blob)| TreeEntry::blob(blob, format!("{id}.json")))
+            .collect();
+        entries.push(TreeEntry {
+            mode: "040000".to_string(),
+            kind: "tree".to_string(),
+            id: git::mktree(&item_entries)?,
+            path: ITEMS_DIR.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+fn add_extra_entries(entries: &mut Vec<TreeEntry>, parents: &[&str]) -> Result<(), String> {
+    const MANAGED: [&str; 4] = [STORE_FILE, ITEMS_DIR, DEPS_FILE, META_FILE];
+    for parent in parents {
+        for entry in git::ls_tree(parent, false)? {
+            if !MANAGED.contains(&entry.path.as_str())
+                && !entries.iter().any(|e| e.path == entry.path)
+            {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Three-way merge of the stores at three commits, returning the merged tree
+/// and the dep conflicts the merge resolved.
+///
+/// When every side uses the per-item layout, an item whose blob is the same
+/// on two sides is resolved from blob IDs alone; only items that changed on
+/// both sides are parsed and merged field by field. Otherwise the whole
+/// stores are loaded and merged with [`store::merge_stores`].
+pub fn merge(
+    base: Option<&str>,
+    ours: &str,
+    theirs: &str,
+    claims: ClaimAuthority,
+) -> Result<(String, Vec<Problem>), String> {
+    let base_entries = match base {
+        Some(commit) => git::ls_tree(commit, true)?,
+        None => Vec::new(),
+    };
+    let our_entries = git::ls_tree(ours, true)?;
+    let their_entries = git::ls_tree(theirs, true)?;
+    if is_single_file(&base_entries)
+        || is_single_file(&our_entries)
+        || is_single_file(&their_entries)
+    {
+        return merge_full(base, ours, theirs, claims);
+    }
+
+    // Everything but the items: deps, settings, schema version
+    let shell = |entries: &[TreeEntry]| -> Result<(u32, Store), String> {
+        let find = |name: &str| entries.iter().find(|e| e.path == name);
+        let (Some(deps), Some(meta)) = (find(DEPS_FILE), find(META_FILE)) else {
+            return Ok((SCHEMA_VERSION, Store::default()));
+        };
+        let contents = git::cat_blobs(&[&deps.id, &meta.id])?;
+        let mut value = meta_object(parse(META_FILE, &contents[1])?)?;
+        value.insert("items".to_string(), Value::Object(Map::new()));
+        value.insert("deps".to_string(), parse(DEPS_FILE, &contents[0])?);
+        let value = Value::Object(value);
+        Ok((migrate::schema_version(&value), store::from_value(value)?))
+    };
+    let (base_version, base_shell) = shell(&base_entries)?;
+    let (our_version, our_shell) = shell(&our_entries)?;
+    let (their_version, their_shell) = shell(&their_entries)?;
+    // Older item files need migrating, which only the full path does
+    if [base_version, our_version, their_version]
+        .iter()
+        .any(|v| *v != SCHEMA_VERSION)
+    {
+        return merge_full(base, ours, theirs, claims);
+    }
+    let mut merged_shell = store::merge_meta(&base_shell, &our_shell, &their_shell);
+    if merged_shell.settings.layout != Layout::PerItem {
+        return merge_full(base, ours, theirs, claims);
+    }
+
+    let item_blobs = |entries: &[TreeEntry]| -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .filter_map(|e| Some((item_id(&e.path)?.to_string(), e.id.clone())))
+            .collect()
+    };
+    let (b, o, t) = (
+        item_blobs(&base_entries),
+        item_blobs(&our_entries),
+        item_blobs(&their_entries),
+    );
+
+    let mut merged: BTreeMap<String, String> = BTreeMap::new();
+    let mut conflicted: Vec<&String> = Vec::new();
+    let ids: BTreeSet<&String> = b.keys().chain(o.keys()).chain(t.keys()).collect();
+    for id in ids {
+        let (bb, ob, tb) = (b.get(id), o.get(id), t.get(id));
+        let pick = if ob == tb {
+            Some(ob)
+        } else if bb == ob && claims == ClaimAuthority::Theirs {
+            Some(tb)
+        } else if bb == tb {
+            Some(ob)
+        } else {
+            None
+        };
+        match pick {
+            Some(Some(blob)) => {
+                merged.insert(id.clone(), blob.clone());
+            }
+            Some(None) => {}
+            None => conflicted.push(id),
+        }
+    }
+
+    // Parse only the items that differ on both sides
+    let mut wanted: Vec<&str> = Vec::new();
+    for id in &conflicted {
+        for side in [&b, &o, &t] {
+            if let Some(blob) = side.get(*id) {
+                wanted.push(blob);
+            }
+        }
+    }
+    let parsed: HashMap<&str, Item> = wanted
+        .iter()
+        .copied()
+        .zip(git::cat_blobs(&wanted)?)
+        .map(|(blob, content)| Ok((blob, parse(&format!("blob {blob}"), &content)?)))
+        .collect::<Result<_, String>>()?;
+    let item = |side: &BTreeMap<String, String>, id: &str| {
+        side.get(id).and_then(|blob| parsed.get(blob.as_str()))
+    };
+    let mut resolved: Vec<Item> = Vec::new();
+    for id in conflicted {
+        if let Some(merged_item) =
+            store::merge_item(item(&b, id), item(&o, id), item(&t, id), claims)
+        {
+            resolved.push(merged_item);
+        }
+    }
+    let contents: Vec<String> = resolved.iter().map(item_json).collect::<Result<_, _>>()?;
+    for (item, blob) in resolved.iter().zip(blobs_for(&contents)?) {
+        merged.insert(item.id.clone(), blob);
+    }
+
+    let (deps, resolved) = store::merge_deps(
+        base_shell.deps(),
+        our_shell.deps(),
+        their_shell.deps(),
+        |id| merged.contains_key(id),
+    );
+    merged_shell.set_deps(deps);
+    let mut entries = per_item_entries(merged, &merged_shell)?;
+    add_extra_entries(&mut entries, &[ours, theirs])?;
+    Ok((git::mktree(&entries)?, resolved))
+}
+
+/// Three-way merge of one file of the store in either layout (for the git
+/// merge driver), telling the file kinds apart by their JSON shape. An empty
+/// `base` means the file was added on both sides. Also returns the dep
+/// conflicts the merge resolved.
+pub fn merge_file(base: &str, ours: &str, theirs: &str) -> Result<(String, Vec<Problem>), String> {
+    fn side<T: serde::de::DeserializeOwned>(
+        name: &str,
+        content: &str,
+    ) -> Result<Option<T>, String> {
+        if content.trim().is_empty() {
+            return Ok(None);
+        }
+        parse(name, content).map(Some)
+    }
+    let shape: serde_json::Value = parse("ours", ours)?;
+    let claims = ClaimAuthority::Theirs;
+    if shape.is_array() {
+        let [b, o, t] = [("base", base), ("ours", ours), ("theirs", theirs)]
+            .map(|(n, c)| side::<Vec<Dep>>(n, c).map(Option::unwrap_or_default));
+        let (deps, resolved) = store::merge_deps(&b?, &o?, &t?, |_| true);
+        let json = serde_json::to_string_pretty(&deps).map_err(|e| e.to_string())?;
+        Ok((json, resolved))
+    } else if shape.get("items").is_some() {
+        let [b, o, t] = [("base", base), ("ours", ours), ("theirs", theirs)]
+            .map(|(n, c)| side::<Store>(n, c).map(Option::unwrap_or_default));
+        let (merged, resolved) = store::merge_stores(&b?, &o?, &t?, claims)?;
+        Ok((store::to_json(&merged)?, resolved))
+    } else if shape.get("id").is_some() {
+        let [b, o, t] =
+            [("base", base), ("ours", ours), ("theirs", theirs)].map(|(n, c)| side::<Item>(n, c));
+        let (b, o, t) = (b?, o?, t?);
+        match store::merge_item(b.as_ref(), o.as_ref(), t.as_ref(), claims) {
+            Some(item) => Ok((item_json(&item)?, Vec::new())),
+            None => Err("item deleted on one side and changed on the other".to_string()),
+        }
+    } else if shape.get("schema_version").is_some() {
+        let [b, o, t] = [("base", base), ("ours", ours), ("theirs", theirs)].map(|(n, c)| {
+            if c.trim().is_empty() {
+                Ok(Store::default())
+            } else {
+                meta_store(n, c)
+            }
+        });
+        let merged = store::merge_meta(&b?, &o?, &t?);
+        Ok((meta_json(&merged)?, Vec::new()))
+    } else {
+        let [b, o, t] = [("base", base), ("ours", ours), ("theirs", theirs)]
+            .map(|(n, c)| side::<Settings>(n, c).map(Option::unwrap_or_default));
+        let settings = store::merge_settings(&b?, &o?, &t?);
+        let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        Ok((json, Vec::new()))
+    }
+}
+
+fn merge_full(
+    base: Option<&str>,
+    ours: &str,
+    theirs: &str,
+    claims: ClaimAuthority,
+) -> Result<(String, Vec<Problem>), String> {
+    let base_store = match base {
+        Some(commit) => load(commit)?,
+        None => Store::default(),
+    };
+    let (merged, resolved) =
+        store::merge_stores(&base_store, &load(ours)?, &load(theirs)?, claims)?;
+    Ok((build_tree(&merged, &[ours, theirs])?, resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_file_by_shape() {
+        let dep = |from: &str| Dep {
+            from_id: from.to_string(),
+            to_id: "lb-zzzz".to_string(),
+            dep_type: crate::model::DepType::Blocks,
+            created_at: None,
+        };
+        let json = |deps: &[Dep]| serde_json::to_string_pretty(deps).unwrap();
+        let merged = merge_file(
+            &json(&[dep("lb-aaaa")]),
+            &json(&[dep("lb-aaaa"), dep("lb-bbbb")]),
+            &json(&[dep("lb-cccc")]),
+        )
+        .unwrap()
+        .0;
+        let deps: Vec<Dep> = serde_json::from_str(&merged).unwrap();
+        assert_eq!(deps, vec![dep("lb-bbbb"), dep("lb-cccc")]);
+
+        let settings = |on: bool| {
+            serde_json::to_string(&Settings {
+                auto_close_parents: on,
+                layout: Layout::PerItem,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let (merged, _) = merge_file("", &settings(false), &settings(true)).unwrap();
+        assert!(merged.contains("\"auto_close_parents\": true"), "{merged}");
+
+        // Versioned meta.json merges setting by setting
+        let meta = |auto_close: bool, layout: Layout| {
+            let mut s = Store::default();
+            s.settings.auto_close_parents = auto_close;
+            s.settings.layout = layout;
+            meta_json(&s).unwrap()
+        };
+        let (merged, _) = merge_file(
+            &meta(false, Layout::SingleFile),
+            &meta(true, Layout::SingleFile),
+            &meta(false, Layout::PerItem),
+        )
+        .unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&merged).unwrap();
+        assert_eq!(merged["schema_version"], SCHEMA_VERSION);
+        assert_eq!(merged["settings"]["auto_close_parents"], true);
+        assert_eq!(merged["settings"]["layout"], "per-item");
+    }
+
+    #[test]
+    fn merge_file_keeps_milestones_from_both_sides() {
+        let meta = |names: &[&str]| {
+            let mut s = Store::default();
+            for name in names {
+                crate::milestone::create(&mut s, name, None).unwrap();
+            }
+            meta_json(&s).unwrap()
+        };
+        let (merged, _) = merge_file(&meta(&[]), &meta(&["v1"]), &meta(&["v2"])).unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&merged).unwrap();
+        let names: Vec<&String> = merged["milestones"].as_object().unwrap().keys().collect();
+        assert_eq!(names, ["v1", "v2"]);
+    }
+
+    #[test]
+    fn item_id_from_path() {
+        assert_eq!(item_id("items/lb-a3f2.json"), Some("lb-a3f2"));
+        assert_eq!(item_id("items.json"), None);
+        assert_eq!(item_id("deps.json"), None);
+        assert_eq!(item_id("items/lb-a3f2.txt"), None);
+    }
+}
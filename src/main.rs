@@ -1,15 +1,30 @@
+mod diff;
+mod due;
+mod fsck;
 mod git;
+mod graph;
 mod id;
+mod layout;
+mod migrate;
+mod milestone;
 mod model;
+mod plan;
+mod progress;
+mod score;
 mod store;
+mod sync;
+mod why;
 
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
 use clap_complete::{Shell, generate};
 use model::{ItemType, Status};
 
 #[derive(Parser)]
 #[command(name = "lb", about = "Litebrite — lightweight issue tracker", version)]
 struct Cli {
+    /// Act as this identity (overrides LB_ACTOR and git config)
+    #[arg(long = "as", global = true, value_name = "WHO")]
+    actor: Option<String>,
     #[command(subcommand)]
     command: Cmd,
 }
@@ -25,6 +40,15 @@ enum Cmd {
         item_type: ItemType,
         #[arg(short, long, default_value_t = 2)]
         priority: u8,
+        /// Expected size: points (3, 5pt) or a duration (4h, 2d)
+        #[arg(short, long)]
+        estimate: Option<model::Estimate>,
+        /// Due date: 2026-11-03, tomorrow, +3d, +2w or a weekday
+        #[arg(long, value_parser = parse_due_arg)]
+        due: Option<chrono::NaiveDate>,
+        /// Schedule for this milestone
+        #[arg(long)]
+        milestone: Option<String>,
         #[arg(long)]
         parent: Option<String>,
         #[arg(short, long)]
@@ -34,13 +58,8 @@ enum Cmd {
     Show { id: String },
     /// List items
     List {
-        /// Show all statuses (default hides closed)
-        #[arg(long)]
-        all: bool,
-        #[arg(short = 't', long = "type")]
-        item_type: Option<ItemType>,
-        #[arg(short, long)]
-        status: Option<Status>,
+        #[command(flatten)]
+        filter: ListFilter,
         /// Display as tree
         #[arg(long)]
         tree: bool,
@@ -48,6 +67,25 @@ enum Cmd {
         #[arg(long)]
         parent: Option<String>,
     },
+    /// Render the dependency graph as Graphviz DOT or Mermaid
+    Graph {
+        /// Draw only this item, its descendants and their direct blockers
+        root: Option<String>,
+        /// Output format: dot or mermaid
+        #[arg(long, default_value = "dot")]
+        format: graph::Format,
+        #[command(flatten)]
+        filter: ListFilter,
+    },
+    /// Order the open work under an item into parallel waves and find its critical path
+    Plan {
+        id: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Break down how far along the work under an item is, child by child
+    Progress { id: String },
     /// Update an item
     Update {
         id: String,
@@ -59,28 +97,177 @@ enum Cmd {
         item_type: Option<ItemType>,
         #[arg(short, long)]
         priority: Option<u8>,
+        /// Expected size: points (3, 5pt) or a duration (4h, 2d); empty to clear
+        #[arg(short, long)]
+        estimate: Option<String>,
+        /// Due date: 2026-11-03, tomorrow, +3d, +2w or a weekday; empty to clear
+        #[arg(long)]
+        due: Option<String>,
+        /// Schedule for this milestone; empty to unschedule
+        #[arg(long)]
+        milestone: Option<String>,
         #[arg(short, long)]
         description: Option<String>,
         #[arg(long)]
         parent: Option<String>,
     },
     /// Close an item (shorthand for --status closed)
-    Close { id: String },
+    Close {
+        id: String,
+        /// Also close all open descendants
+        #[arg(long)]
+        cascade: bool,
+    },
     /// Delete an item and its deps
     Delete { id: String },
+    /// Manage milestones
+    Milestone {
+        #[command(subcommand)]
+        action: MilestoneCmd,
+    },
     /// Manage dependencies
     Dep {
         #[command(subcommand)]
         action: DepCmd,
     },
-    /// Show open + unblocked + unclaimed items sorted by priority
-    Ready,
+    /// Show open + unblocked + unclaimed items, best first
+    Ready {
+        /// Show your own claimed items that are unblocked instead
+        #[arg(long)]
+        mine: bool,
+        /// Show how many items each would unblock, and put the most first
+        #[arg(long)]
+        unblocks: bool,
+        /// Show how each item's score adds up
+        #[arg(long, conflicts_with = "unblocks")]
+        explain: bool,
+        /// Only items scheduled for this milestone
+        #[arg(long)]
+        milestone: Option<String>,
+    },
+    /// Explain why an item isn't in `lb ready`
+    Why { id: String },
+    /// List blocked items with the blockers at the end of their chains
+    Blocked,
+    /// List items that closing this one would unblock, directly and in turn
+    Unblocks { id: String },
+    /// Claim the best ready item, moving on to the next if someone beats us to it
+    Next {
+        /// Only consider items of this type
+        #[arg(short = 't', long = "type")]
+        item_type: Option<ItemType>,
+        /// Only consider items under this parent, at any depth
+        #[arg(long)]
+        parent: Option<String>,
+        /// Lease length (e.g. 30m, 4h, 2d); the claim lapses if not renewed
+        #[arg(long, value_parser = store::parse_ttl)]
+        ttl: Option<u64>,
+    },
     /// Claim an item (fetch + set claimed_by + push)
-    Claim { id: String },
+    Claim {
+        id: String,
+        /// Lease length (e.g. 30m, 4h, 2d); the claim lapses if not renewed
+        #[arg(long, value_parser = store::parse_ttl)]
+        ttl: Option<u64>,
+        /// Restart the lease on a claim you already hold
+        #[arg(long, conflicts_with = "steal")]
+        renew: bool,
+        /// Take over a claim held by someone else (recorded in history)
+        #[arg(long)]
+        steal: bool,
+        /// Why the claim is being taken over
+        #[arg(long, requires = "steal")]
+        reason: Option<String>,
+        /// Queue the claim locally without contacting the remote; `lb sync`
+        /// confirms it later
+        #[arg(long, conflicts_with_all = ["renew", "steal"])]
+        offline: bool,
+    },
+    /// Hand a claim over to someone else (fetch + reassign + push)
+    Handoff {
+        id: String,
+        /// Identity that takes over the claim
+        to: String,
+        /// Why the claim is being handed off
+        #[arg(long)]
+        reason: Option<String>,
+        /// Allow handing off a claim held by someone other than you
+        #[arg(long)]
+        force: bool,
+    },
     /// Unclaim an item (fetch + clear claimed_by + push)
-    Unclaim { id: String },
+    Unclaim {
+        id: String,
+        /// Allow releasing a claim held by someone else (recorded in history)
+        #[arg(long)]
+        force: bool,
+        /// Why the claim is being released
+        #[arg(long, requires = "force")]
+        reason: Option<String>,
+    },
+    /// List claimed items with their lease status
+    Claims {
+        /// Show only claims whose lease has expired
+        #[arg(long)]
+        stale: bool,
+        /// Release stale claims so the items become ready again
+        #[arg(long, requires = "stale")]
+        release: bool,
+    },
     /// Sync local changes with remote (fetch + merge + push)
-    Sync,
+    Sync {
+        /// Sync only with this remote (default: every `litebrite.remote`, or origin)
+        #[arg(long)]
+        remote: Option<String>,
+    },
+    /// Show item, dependency and setting changes between two versions of the store
+    Diff {
+        /// Older side (default: the local branch)
+        ref_a: Option<String>,
+        /// Newer side (default: the primary remote's branch, or the local
+        /// branch when <REF_A> is given)
+        ref_b: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Render a store file one field per line, for use as a git textconv
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["ref_a", "ref_b", "json"])]
+        textconv: Option<std::path::PathBuf>,
+    },
+    /// Merge three versions of a store file (git merge driver: %O %A %B)
+    MergeDriver {
+        /// Common ancestor version
+        base: std::path::PathBuf,
+        /// Our version; overwritten with the merge result
+        ours: std::path::PathBuf,
+        /// Their version
+        theirs: std::path::PathBuf,
+    },
+    /// Show how the local tracker compares to its remotes (no network by default)
+    Status {
+        /// Fetch from each remote first
+        #[arg(long)]
+        fetch: bool,
+    },
+    /// Check the store for broken invariants (dangling deps, cycles, ...)
+    Fsck {
+        /// Fix every problem found, in one commit
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Upgrade the store to the current schema version
+    Migrate {
+        /// Show the migrations that would run without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show or change repository settings
+    Config {
+        /// Setting to show or change (omit to list all)
+        key: Option<String>,
+        /// New value for the setting
+        value: Option<String>,
+    },
     /// Output AI-optimized context for Claude Code hooks
     Prime,
     /// Set up integrations
@@ -93,6 +280,30 @@ enum Cmd {
     Completions { shell: Shell },
 }
 
+/// Item filters shared by the list views.
+#[derive(Args, Default)]
+struct ListFilter {
+    /// Show all statuses (default hides closed)
+    #[arg(long)]
+    all: bool,
+    #[arg(short = 't', long = "type")]
+    item_type: Option<ItemType>,
+    #[arg(short, long)]
+    status: Option<Status>,
+    /// Show only items claimed by this identity
+    #[arg(long, value_name = "WHO")]
+    claimed_by: Option<String>,
+    /// Show only open items past their due date
+    #[arg(long)]
+    overdue: bool,
+    /// Show only items due before this date (2026-11-03, +3d, friday, ...)
+    #[arg(long, value_name = "DATE", value_parser = parse_due_arg)]
+    due_before: Option<chrono::NaiveDate>,
+    /// Show only items scheduled for this milestone
+    #[arg(long, value_name = "NAME")]
+    milestone: Option<String>,
+}
+
 #[derive(Subcommand)]
 enum DepCmd {
     /// Add a blocking dependency
@@ -107,10 +318,33 @@ enum DepCmd {
     List { id: String },
 }
 
+#[derive(Subcommand)]
+enum MilestoneCmd {
+    /// Create a milestone
+    Create {
+        name: String,
+        /// Target date: 2026-11-03, +2w, friday, ...
+        #[arg(long, value_parser = parse_due_arg)]
+        target: Option<chrono::NaiveDate>,
+    },
+    /// List milestones with their open and closed item counts
+    List {
+        /// Include closed milestones
+        #[arg(long)]
+        all: bool,
+    },
+    /// Report on a milestone: progress, its items, and what blocks them from outside
+    Show { name: String },
+    /// Close a milestone
+    Close { name: String },
+}
+
 #[derive(Subcommand)]
 enum SetupCmd {
     /// Set up Claude Code integration (hooks + permissions)
     Claude,
+    /// Register the merge driver and diff textconv so plain git handles the store
+    Git,
 }
 
 fn main() {
@@ -122,10 +356,11 @@ fn main() {
 }
 
 fn run(cli: Cli) -> Result<(), String> {
+    let explicit_actor = cli.actor;
     match cli.command {
         Cmd::Init => {
-            let empty_store = store::to_json(&model::Store::default())?;
-            git::init_branch(&empty_store)?;
+            let empty_tree = layout::build_tree(&model::Store::default(), &[])?;
+            git::init_branch(&empty_tree, &sync::primary_remote())?;
             println!("initialized litebrite branch");
             Ok(())
         }
@@ -133,11 +368,26 @@ fn run(cli: Cli) -> Result<(), String> {
             title,
             item_type,
             priority,
+            estimate,
+            due,
+            milestone,
             parent,
             description,
         } => {
             let mut s = load()?;
-            let id = store::create_item(&mut s, title, item_type, priority, description, parent)?;
+            let id = store::create_item(
+                &mut s,
+                title,
+                item_type,
+                priority,
+                store::Schedule {
+                    estimate,
+                    due,
+                    milestone,
+                },
+                description,
+                parent,
+            )?;
             save(&s, &format!("Create item {id}"))?;
             println!("created {id}");
             Ok(())
@@ -145,67 +395,17 @@ fn run(cli: Cli) -> Result<(), String> {
         Cmd::Show { id } => {
             let s = load()?;
             let id = store::resolve_id(&s, &id)?;
-            let item = s.items.get(&id).ok_or("item not found")?;
-            println!("  ID: {}", item.id);
-            println!("  Title: {}", item.title);
-            println!("  Type: {}", item.item_type);
-            println!("  Status: {}", item.status);
-            println!("  Priority: P{}", item.priority);
-            if let Some(ref who) = item.claimed_by {
-                println!("  Claimed by: {who}");
-            }
-            if let Some(ref desc) = item.description {
-                println!("  Description: {desc}");
-            }
-            println!("  Created: {}", item.created_at.format("%Y-%m-%d %H:%M"));
-            println!("  Updated: {}", item.updated_at.format("%Y-%m-%d %H:%M"));
-
-            if let Some(pid) = store::get_parent(&s, &id)
-                && let Some(p) = s.items.get(&pid)
-            {
-                println!("  Parent: {} ({})", pid, p.title);
-            }
-
-            let children = store::get_children(&s, &id);
-            if !children.is_empty() {
-                println!("  Children:");
-                for cid in &children {
-                    if let Some(c) = s.items.get(cid) {
-                        println!("    {} [{}] {}", cid, c.status, c.title);
-                    }
-                }
-            }
-
-            let blockers = store::get_blockers(&s, &id);
-            if !blockers.is_empty() {
-                println!("  Blocked by:");
-                for bid in &blockers {
-                    if let Some(b) = s.items.get(bid) {
-                        println!("    {} [{}] {}", bid, b.status, b.title);
-                    }
-                }
-            }
-
-            let blocking = store::get_blocking(&s, &id);
-            if !blocking.is_empty() {
-                println!("  Blocks:");
-                for bid in &blocking {
-                    if let Some(b) = s.items.get(bid) {
-                        println!("    {} [{}] {}", bid, b.status, b.title);
-                    }
-                }
-            }
-
-            Ok(())
+            print_item_details(&s, &id)
         }
         Cmd::List {
-            all,
-            item_type,
-            status,
+            filter,
             tree,
             parent,
         } => {
             let s = load()?;
+            if let Some(ref name) = filter.milestone {
+                milestone::get(&s, name)?;
+            }
             if let Some(pid) = parent {
                 let pid = store::resolve_id(&s, &pid)?;
                 let child_ids = store::get_children(&s, &pid);
@@ -214,45 +414,169 @@ fn run(cli: Cli) -> Result<(), String> {
                     child_ids.iter().filter_map(|id| s.items.get(id)).collect();
                 children.sort_by_key(|i| (i.priority, i.id.clone()));
                 for item in children {
-                    if should_show(item, all, item_type, status) {
-                        print_list_row(item);
+                    if should_show(item, &filter) {
+                        print_list_row(&s, item);
                     }
                 }
             } else if tree {
                 let roots = store::root_items(&s);
                 for root in &roots {
-                    print_tree_item(&s, &root.id, 0, all, item_type, status);
+                    print_tree_item(&s, &root.id, 0, &filter);
                 }
             } else {
                 print_list_header();
                 let mut items: Vec<&model::Item> = s.items.values().collect();
                 items.sort_by_key(|i| (i.priority, i.id.clone()));
                 for item in items {
-                    if should_show(item, all, item_type, status) {
-                        print_list_row(item);
+                    if should_show(item, &filter) {
+                        print_list_row(&s, item);
                     }
                 }
             }
             Ok(())
         }
+        Cmd::Progress { id } => {
+            let s = load()?;
+            let id = store::resolve_id(&s, &id)?;
+            let now = chrono::Utc::now();
+            let children = store::get_children(&s, &id);
+            if children.is_empty() {
+                println!("{id} has no children");
+                return Ok(());
+            }
+            println!(
+                "{id} {}: {}\n",
+                s.items[&id].title,
+                progress::rollup(&s, &id, now)
+            );
+            println!(
+                "{:<10} {:<14} {:<10} {:<8} {:<8} {:<10} TITLE",
+                "ID", "STATUS", "CLOSED", "CLAIMED", "BLOCKED", "ESTIMATE"
+            );
+            println!("{}", "-".repeat(81));
+            let mut rows: Vec<&model::Item> =
+                children.iter().filter_map(|c| s.items.get(c)).collect();
+            rows.sort_by_key(|i| (i.status == Status::Closed, i.priority, i.id.clone()));
+            for child in rows {
+                let r = progress::subtree(&s, &child.id, now);
+                let estimate = match r.estimate_percent() {
+                    Some(pct) => format!("{} {pct}%", r.estimated),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "{:<10} {:<14} {:<10} {:<8} {:<8} {:<10} {}",
+                    child.id,
+                    status_label(child),
+                    format!("{}/{}", r.closed, r.total),
+                    r.claimed,
+                    r.blocked,
+                    estimate,
+                    child.title
+                );
+            }
+            Ok(())
+        }
+        Cmd::Graph {
+            root,
+            format,
+            filter,
+        } => {
+            let s = load()?;
+            let root = root.map(|r| store::resolve_id(&s, &r)).transpose()?;
+            let items = graph::select(&s, root.as_deref(), |item| should_show(item, &filter));
+            print!("{}", graph::render(&s, &items, format));
+            Ok(())
+        }
+        Cmd::Plan { id, json } => {
+            let s = load()?;
+            let id = store::resolve_id(&s, &id)?;
+            // Unestimated items count as one point (or hour) of work
+            let plan = plan::plan(&s, &id, |i| i.estimate.map_or(1.0, |e| e.weight()))?;
+            if json {
+                let out = serde_json::to_string_pretty(&plan).map_err(|e| e.to_string())?;
+                println!("{out}");
+                return Ok(());
+            }
+            if plan.order.is_empty() {
+                println!("no open work under {id}");
+                return Ok(());
+            }
+            let widest = plan.waves.iter().map(Vec::len).max().unwrap_or(0);
+            println!(
+                "{id} {}: {} open item(s) in {} wave(s), up to {widest} in parallel",
+                s.items[&id].title,
+                plan.order.len(),
+                plan.waves.len()
+            );
+            for (n, wave) in plan.waves.iter().enumerate() {
+                println!("\nWave {} ({} agent(s)):", n + 1, wave.len());
+                for wid in wave {
+                    let item = &s.items[wid];
+                    let mut line = format!(
+                        "  {:<10} {:<8} P{}  {}",
+                        item.id, item.item_type, item.priority, item.title
+                    );
+                    if let Some(est) = item.estimate {
+                        line.push_str(&format!("  [{est}]"));
+                    }
+                    if let Some(outside) = plan.external_blockers.get(wid) {
+                        line.push_str(&format!("  (waits on {})", outside.join(", ")));
+                    }
+                    println!("{line}");
+                }
+            }
+            let estimated = plan.order.iter().any(|i| s.items[i].estimate.is_some());
+            let weight = if estimated {
+                format!(", weight {}", plan.critical_path_weight)
+            } else {
+                String::new()
+            };
+            println!(
+                "\nCritical path ({} item(s){weight}): {}",
+                plan.critical_path.len(),
+                plan.critical_path.join(" -> ")
+            );
+            Ok(())
+        }
         Cmd::Update {
             id,
             title,
             status,
             item_type,
             priority,
+            estimate,
+            due,
+            milestone,
             description,
             parent,
         } => {
             let mut s = load()?;
             let id = store::resolve_id(&s, &id)?;
+            let due = due
+                .map(|d| {
+                    if d.is_empty() {
+                        Ok(None)
+                    } else {
+                        parse_due_arg(&d).map(Some)
+                    }
+                })
+                .transpose()?;
+            let estimate = estimate
+                .map(|e| {
+                    if e.is_empty() {
+                        Ok(None)
+                    } else {
+                        e.parse::<model::Estimate>().map(Some)
+                    }
+                })
+                .transpose()?;
             {
                 let item = s.items.get_mut(&id).ok_or("item not found")?;
                 if let Some(t) = title {
                     item.title = t;
                 }
-                if let Some(st) = status {
-                    item.status = st;
+                if status == Some(Status::Open) {
+                    item.status = Status::Open;
                 }
                 if let Some(it) = item_type {
                     item.item_type = it;
@@ -260,24 +584,54 @@ fn run(cli: Cli) -> Result<(), String> {
                 if let Some(p) = priority {
                     item.priority = p;
                 }
+                if let Some(e) = estimate {
+                    item.estimate = e;
+                }
+                if let Some(d) = due {
+                    item.due = d;
+                }
                 if let Some(d) = description {
                     item.description = if d.is_empty() { None } else { Some(d) };
                 }
                 item.updated_at = chrono::Utc::now();
             }
+            if let Some(name) = milestone {
+                milestone::assign(&mut s, &id, Some(name.as_str()).filter(|n| !n.is_empty()))?;
+            }
             if let Some(pid) = parent {
                 store::set_parent(&mut s, &id, &pid)?;
             }
+            // Closing goes through close_item so the open-children check and
+            // auto_close_parents apply as they do for `lb close`
+            let closed = if status == Some(Status::Closed) && s.items[&id].status == Status::Open {
+                store::close_item(&mut s, &id)?
+            } else {
+                Vec::new()
+            };
             save(&s, &format!("Update item {id}"))?;
             println!("updated {id}");
+            for pid in closed.iter().skip(1) {
+                println!("closed {pid}");
+            }
             Ok(())
         }
-        Cmd::Close { id } => {
+        Cmd::Close { id, cascade } => {
             let mut s = load()?;
             let id = store::resolve_id(&s, &id)?;
-            store::close_item(&mut s, &id)?;
-            save(&s, &format!("Close item {id}"))?;
-            println!("closed {id}");
+            let closed = if cascade {
+                store::close_cascade(&mut s, &id)?
+            } else {
+                store::close_item(&mut s, &id)?
+            };
+            let message = if closed.len() > 1 {
+                format!("Close item {id} (+{} more)", closed.len() - 1)
+            } else {
+                format!("Close item {id}")
+            };
+            save(&s, &message)?;
+            for cid in &closed {
+                println!("closed {cid}");
+            }
             Ok(())
         }
         Cmd::Delete { id } => {
@@ -290,6 +644,98 @@ fn run(cli: Cli) -> Result<(), String> {
             }
             Ok(())
         }
+        Cmd::Milestone { action } => match action {
+            MilestoneCmd::Create { name, target } => {
+                let mut s = load()?;
+                milestone::create(&mut s, &name, target)?;
+                let name = name.trim();
+                save(&s, &format!("Create milestone {name}"))?;
+                println!("created milestone {name}");
+                Ok(())
+            }
+            MilestoneCmd::List { all } => {
+                let s = load()?;
+                let counts = milestone::counts(&s);
+                let shown: Vec<&model::Milestone> = s
+                    .milestones
+                    .values()
+                    .filter(|m| all || m.state == model::MilestoneState::Open)
+                    .collect();
+                if shown.is_empty() {
+                    println!("no milestones");
+                    return Ok(());
+                }
+                println!(
+                    "{:<16} {:<8} {:<12} {:<6} CLOSED",
+                    "NAME", "STATE", "TARGET", "OPEN"
+                );
+                println!("{}", "-".repeat(52));
+                for m in shown {
+                    let (open, closed) = counts[m.name.as_str()];
+                    let target = m.target.map(|t| t.to_string()).unwrap_or_default();
+                    println!(
+                        "{:<16} {:<8} {:<12} {:<6} {closed}",
+                        m.name, m.state, target, open
+                    );
+                }
+                Ok(())
+            }
+            MilestoneCmd::Show { name } => {
+                let s = load()?;
+                let m = milestone::get(&s, &name)?;
+                let report = milestone::report(&s, &name);
+                println!("  Milestone: {}", m.name);
+                println!("  State: {}", m.state);
+                if let Some(target) = m.target {
+                    println!(
+                        "  Target: {target} ({})",
+                        due::describe(target, due::today())
+                    );
+                }
+                println!("  Items: {} open, {} closed", report.open, report.closed);
+                if !report.items.is_empty() {
+                    println!();
+                    print_list_header();
+                    for id in &report.items {
+                        print_list_row(&s, &s.items[id]);
+                    }
+                }
+                if !report.blocked_outside.is_empty() {
+                    println!("\nBlocked by work outside {name}:");
+                    for (id, blockers) in &report.blocked_outside {
+                        println!("  {id} {}", s.items[id].title);
+                        for bid in blockers {
+                            match s.items.get(bid) {
+                                Some(b) => {
+                                    let scheduled = match b.milestone.as_deref() {
+                                        Some(other) => format!("milestone {other}"),
+                                        None => "no milestone".to_string(),
+                                    };
+                                    println!(
+                                        "    blocked by {bid} [{}, {scheduled}] {}",
+                                        b.status, b.title
+                                    );
+                                }
+                                None => println!("    blocked by {bid} (missing)"),
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
+            MilestoneCmd::Close { name } => {
+                let mut s = load()?;
+                milestone::close(&mut s, &name)?;
+                save(&s, &format!("Close milestone {name}"))?;
+                let open = milestone::report(&s, &name).open;
+                if open > 0 {
+                    println!("closed milestone {name} ({open} item(s) still open)");
+                } else {
+                    println!("closed milestone {name}");
+                }
+                Ok(())
+            }
+        },
         Cmd::Dep { action } => match action {
             DepCmd::Add { blocker, blocks } => {
                 let mut s = load()?;
@@ -350,199 +796,620 @@ fn run(cli: Cli) -> Result<(), String> {
                 Ok(())
             }
         },
-        Cmd::Ready => {
+        Cmd::Ready {
+            mine,
+            unblocks,
+            explain,
+            milestone,
+        } => {
             let s = load()?;
-            let items = store::ready_items(&s);
+            let mut items = if mine {
+                let me = resolve_actor(explicit_actor)?;
+                store::ready_claimed_by(&s, &me)
+            } else {
+                store::ready_items(&s)
+            };
+            if let Some(ref name) = milestone {
+                milestone::get(&s, name)?;
+                items.retain(|i| i.milestone.as_deref() == Some(name.as_str()));
+            }
             if items.is_empty() {
-                println!("no ready items");
+                println!(
+                    "{}",
+                    if mine {
+                        "no ready claimed items"
+                    } else {
+                        "no ready items"
+                    }
+                );
+            } else if unblocks {
+                let counts: std::collections::HashMap<&str, usize> = items
+                    .iter()
+                    .map(|i| (i.id.as_str(), why::unblocks(&s, &i.id).len()))
+                    .collect();
+                // Stable, so equal counts keep priority order
+                items.sort_by_key(|i| std::cmp::Reverse(counts[i.id.as_str()]));
+                println!(
+                    "{:<10} {:<8} {:<14} {:<4} {:<12} {:<8} TITLE",
+                    "ID", "TYPE", "STATUS", "PRI", "DUE", "UNBLOCKS"
+                );
+                println!("{}", "-".repeat(82));
+                for item in items {
+                    println!(
+                        "{:<10} {:<8} {:<14} {:<4} {:<12} {:<8} {}",
+                        item.id,
+                        item.item_type,
+                        status_label(item),
+                        format!("P{}", item.priority),
+                        due_label(&s, item),
+                        counts[item.id.as_str()],
+                        item.title
+                    );
+                }
             } else {
+                let now = chrono::Utc::now();
+                let weights = s.settings.score;
                 print_list_header();
-                for item in items {
-                    print_list_row(item);
+                for (item, score) in score::rank(items, |i| score::score(&s, i, &weights, now)) {
+                    print_list_row(&s, item);
+                    if explain {
+                        println!("{:>10} score {score}", "");
+                    }
                 }
             }
             Ok(())
         }
-        Cmd::Claim { id } => {
-            let has_remote = sync_from_remote()?;
-
-            let mut s = load()?;
+        Cmd::Unblocks { id } => {
+            let s = load()?;
             let id = store::resolve_id(&s, &id)?;
-            let item = s.items.get(&id).ok_or("item not found")?;
-
-            if item.status == Status::Closed {
-                return Err(format!("item {id} is closed"));
+            let freed = why::unblocks(&s, &id);
+            if freed.is_empty() {
+                println!("closing {id} unblocks nothing");
+                return Ok(());
             }
-            if let Some(ref who) = item.claimed_by {
-                return Err(format!("item {id} already claimed by {who}"));
+            println!("closing {id} unblocks {} item(s):", freed.len());
+            let (direct, later): (Vec<_>, Vec<_>) = freed.iter().partition(|(_, d)| *d == 1);
+            for (heading, group) in [("directly", direct), ("once those close", later)] {
+                if group.is_empty() {
+                    continue;
+                }
+                println!("\n{heading}:");
+                print_list_header();
+                for (fid, _) in group {
+                    print_list_row(&s, &s.items[fid]);
+                }
             }
-
-            let user = git::git_user_name()?;
-            let item = s.items.get_mut(&id).ok_or("item not found")?;
-            item.claimed_by = Some(user.clone());
-            item.updated_at = chrono::Utc::now();
-            save(&s, &format!("{user} claims {id}"))?;
-
-            if has_remote {
-                // Push — retry once on conflict
-                match git::push() {
-                    Ok(()) => {}
-                    Err(_) => {
-                        // Push rejected — fetch and check if someone else claimed it
-                        git::fetch().map_err(|e| format!("fetch failed on retry: {e}"))?;
-                        let remote_json =
-                            git::read_store_from_ref("refs/remotes/origin/litebrite")?;
-                        let remote_store = store::from_json(&remote_json)?;
-                        if let Some(remote_item) = remote_store.items.get(&id)
-                            && let Some(ref who) = remote_item.claimed_by
+            Ok(())
+        }
+        Cmd::Why { id } => {
+            let s = load()?;
+            let id = store::resolve_id(&s, &id)?;
+            let reasons = why::why_not_ready(&s, &id, chrono::Utc::now());
+            if reasons.is_empty() {
+                println!("{id} is ready");
+                return Ok(());
+            }
+            println!("{id} is not ready:");
+            for reason in reasons {
+                match reason {
+                    why::Reason::Closed => println!("  closed"),
+                    why::Reason::Claimed { who, expires_at } => match expires_at {
+                        Some(at) => println!(
+                            "  claimed by {who} (lease expires {})",
+                            at.format("%Y-%m-%d %H:%M")
+                        ),
+                        None => println!("  claimed by {who}"),
+                    },
+                    why::Reason::Blocked(blocker) => print_blocker(&s, &blocker, 1),
+                }
+            }
+            Ok(())
+        }
+        Cmd::Blocked => {
+            let s = load()?;
+            let blocked = why::blocked_items(&s);
+            if blocked.is_empty() {
+                println!("no blocked items");
+                return Ok(());
+            }
+            println!("{:<10} {:<30} ROOT BLOCKERS", "ID", "TITLE");
+            println!("{}", "-".repeat(60));
+            for id in blocked {
+                let roots: Vec<String> = why::root_blockers(&s, id)
+                    .into_iter()
+                    .map(|r| {
+                        if s.items.contains_key(&r) {
+                            r
+                        } else {
+                            format!("{r} (missing)")
+                        }
+                    })
+                    .collect();
+                println!("{:<10} {:<30} {}", id, s.items[id].title, roots.join(", "));
+            }
+            Ok(())
+        }
+        Cmd::Next {
+            item_type,
+            parent,
+            ttl,
+        } => {
+            let primary = sync::primary_remote();
+            let has_remote = sync::from_remote(&primary)?;
+            let user = resolve_actor(explicit_actor)?;
+            let mut lost: Vec<String> = Vec::new();
+            loop {
+                let mut s = load()?;
+                let parent = parent
+                    .as_deref()
+                    .map(|p| store::resolve_id(&s, p))
+                    .transpose()?;
+                let now = chrono::Utc::now();
+                let weights = s.settings.score;
+                let candidates: Vec<&model::Item> = store::ready_items(&s)
+                    .into_iter()
+                    .filter(|i| item_type.is_none_or(|t| i.item_type == t))
+                    .filter(|i| {
+                        parent
+                            .as_deref()
+                            .is_none_or(|p| store::is_descendant(&s, &i.id, p))
+                    })
+                    .filter(|i| !lost.contains(&i.id))
+                    .collect();
+                let Some((best, _)) =
+                    score::rank(candidates, |i| score::score(&s, i, &weights, now))
+                        .into_iter()
+                        .next()
+                else {
+                    println!("no ready items");
+                    return Ok(());
+                };
+                let id = best.id.clone();
+
+                store::claim_item(&mut s, &id, &user, ttl, now)?;
+                let message = format!("{user} claims {id}");
+                save(&s, &message)?;
+                if has_remote {
+                    let winner = std::cell::RefCell::new(None);
+                    let pushed = sync::push_with_retry(&primary, &message, |remote| {
+                        if let Some(who) = remote
+                            .items
+                            .get(&id)
+                            .and_then(|i| store::active_claimant(i, now))
+                            .filter(|w| *w != user)
                         {
+                            *winner.borrow_mut() = Some(who.to_string());
                             return Err(format!("item {id} already claimed by {who}"));
                         }
-
-                        // Not a claim conflict — try merge and push
-                        let base_commit = git::merge_base()?;
-                        let base_store = match base_commit {
-                            Some(ref commit) => {
-                                let json = git::read_store_from_ref(commit)?;
-                                store::from_json(&json)?
-                            }
-                            None => model::Store::default(),
+                        Ok(())
+                    });
+                    if let Err(e) = pushed {
+                        let Some(who) = winner.take() else {
+                            return Err(e);
                         };
-                        let merged = store::merge_stores(&base_store, &s, &remote_store)?;
-                        let merged_json = store::to_json(&merged)?;
-
-                        let local_ref = git::local_ref()?;
-                        let remote_ref = git::remote_ref()?;
-                        git::create_merge_commit(
-                            &merged_json,
-                            &local_ref,
-                            &remote_ref,
-                            &format!("Merge: {user} claims {id}"),
-                        )?;
-                        git::push().map_err(|e| format!("push failed after merge: {e}"))?;
+                        // Their claim wins the merge, so the local branch
+                        // agrees with the remote before we try again
+                        sync::integrate(&primary, &format!("Merge: {who} claimed {id} first"))?;
+                        eprintln!("{id} was claimed by {who} first, trying the next item");
+                        lost.push(id);
+                        continue;
                     }
                 }
+
+                println!("claimed {id} ({user})\n");
+                return print_item_details(&load()?, &id);
+            }
+        }
+        Cmd::Claim {
+            id,
+            ttl,
+            renew,
+            steal,
+            reason,
+            offline,
+        } => {
+            if offline {
+                let s = load()?;
+                let id = store::resolve_id(&s, &id)?;
+                let user = resolve_actor(explicit_actor)?;
+                let now = chrono::Utc::now();
+                if store::claim_status(&s, &id, &user, now)? {
+                    println!("{id} is already claimed by you ({user})");
+                    return Ok(());
+                }
+                sync::queue_pending(sync::PendingClaim {
+                    id: id.clone(),
+                    actor: user.clone(),
+                    ttl,
+                    queued_at: now,
+                })?;
+                println!("queued offline claim on {id} ({user}) — run `lb sync` to confirm");
+                return Ok(());
+            }
+
+            let primary = sync::primary_remote();
+            let has_remote = sync::from_remote(&primary)
+                .map_err(|e| format!("{e} (offline? `lb claim --offline` queues the claim)"))?;
+
+            let mut s = load()?;
+            let id = store::resolve_id(&s, &id)?;
+            let user = resolve_actor(explicit_actor)?;
+            let now = chrono::Utc::now();
+            let mut stolen_from = None;
+            let message = if renew {
+                store::renew_claim(&mut s, &id, &user, ttl, now)?;
+                format!("{user} renews claim on {id}")
+            } else if steal {
+                let prev = store::steal_claim(&mut s, &id, &user, reason, ttl, now)?;
+                let message = format!("{user} steals {id} from {prev}");
+                stolen_from = Some(prev);
+                message
+            } else {
+                store::claim_item(&mut s, &id, &user, ttl, now)?;
+                format!("{user} claims {id}")
+            };
+            save(&s, &message)?;
+
+            if has_remote {
+                sync::push_with_retry(&primary, &message, |remote| {
+                    let Some(remote_item) = remote.items.get(&id) else {
+                        return Ok(());
+                    };
+                    // A steal only stands if the claim we took over is still the
+                    // one on the remote; otherwise any other active claim wins.
+                    let who = match stolen_from {
+                        Some(ref prev) => remote_item
+                            .claimed_by
+                            .as_deref()
+                            .filter(|w| w != prev && *w != user),
+                        None => store::active_claimant(remote_item, now).filter(|w| *w != user),
+                    };
+                    match who {
+                        Some(who) => Err(format!("item {id} already claimed by {who}")),
+                        None => Ok(()),
+                    }
+                })?;
             }
 
-            println!("claimed {id} ({user})");
+            let lease = s.items[&id]
+                .claim_ttl
+                .map(|t| format!(", expires in {}", store::format_duration(t as i64)))
+                .unwrap_or_default();
+            if renew {
+                println!("renewed claim on {id} ({user}{lease})");
+            } else if let Some(prev) = stolen_from {
+                println!("claimed {id} ({user}{lease}), taken over from {prev}");
+            } else {
+                println!("claimed {id} ({user}{lease})");
+            }
             Ok(())
         }
-        Cmd::Unclaim { id } => {
-            let has_remote = sync_from_remote()?;
+        Cmd::Handoff {
+            id,
+            to,
+            reason,
+            force,
+        } => {
+            let primary = sync::primary_remote();
+            let has_remote = sync::from_remote(&primary)?;
 
             let mut s = load()?;
             let id = store::resolve_id(&s, &id)?;
-            let item = s.items.get(&id).ok_or("item not found")?;
+            let user = resolve_actor(explicit_actor)?;
+            let prev =
+                store::handoff_claim(&mut s, &id, &user, &to, reason, force, chrono::Utc::now())?;
+            let message = format!("{user} hands off {id} to {to}");
+            save(&s, &message)?;
+
+            if has_remote {
+                sync::push_with_retry(&primary, &message, |remote| {
+                    match remote.items.get(&id).and_then(|i| i.claimed_by.as_deref()) {
+                        Some(who) if Some(who) != prev.as_deref() && who != to => Err(format!(
+                            "claim on {id} changed remotely (now {who}) — retry"
+                        )),
+                        _ => Ok(()),
+                    }
+                })?;
+            }
 
-            if item.claimed_by.is_none() {
-                return Err(format!("item {id} is not claimed"));
+            match prev {
+                Some(prev) => println!("handed off {id} from {prev} to {to}"),
+                None => println!("handed off {id} to {to}"),
             }
+            Ok(())
+        }
+        Cmd::Unclaim { id, force, reason } => {
+            let primary = sync::primary_remote();
+            let has_remote = sync::from_remote(&primary)?;
 
-            let item = s.items.get_mut(&id).ok_or("item not found")?;
-            item.claimed_by = None;
-            item.updated_at = chrono::Utc::now();
-            save(&s, &format!("Unclaim {id}"))?;
+            let mut s = load()?;
+            let id = store::resolve_id(&s, &id)?;
+            let user = resolve_actor(explicit_actor)?;
+            store::release_claim(&mut s, &id, &user, force, reason, chrono::Utc::now())?;
+            let message = format!("Unclaim {id}");
+            save(&s, &message)?;
 
             if has_remote {
-                // Push — retry once on conflict
-                match git::push() {
-                    Ok(()) => {}
-                    Err(_) => {
-                        git::fetch().map_err(|e| format!("fetch failed on retry: {e}"))?;
-                        let remote_json =
-                            git::read_store_from_ref("refs/remotes/origin/litebrite")?;
-                        let remote_store = store::from_json(&remote_json)?;
-
-                        let base_commit = git::merge_base()?;
-                        let base_store = match base_commit {
-                            Some(ref commit) => {
-                                let json = git::read_store_from_ref(commit)?;
-                                store::from_json(&json)?
-                            }
-                            None => model::Store::default(),
-                        };
-                        let merged = store::merge_stores(&base_store, &s, &remote_store)?;
-                        let merged_json = store::to_json(&merged)?;
-
-                        let local_ref = git::local_ref()?;
-                        let remote_ref = git::remote_ref()?;
-                        git::create_merge_commit(
-                            &merged_json,
-                            &local_ref,
-                            &remote_ref,
-                            &format!("Merge: unclaim {id}"),
-                        )?;
-                        git::push().map_err(|e| format!("push failed after merge: {e}"))?;
-                    }
-                }
+                sync::push_with_retry(&primary, &message, |_| Ok(()))?;
             }
 
             println!("unclaimed {id}");
             Ok(())
         }
-        Cmd::Sync => {
-            if !git::has_remote() {
-                return Err("no remote configured — nothing to sync".to_string());
+        Cmd::Claims { stale, release } => {
+            let primary = sync::primary_remote();
+            let has_remote = release && sync::from_remote(&primary)?;
+            let mut s = load()?;
+            let now = chrono::Utc::now();
+            if release {
+                let released = store::release_stale_claims(&mut s, now);
+                if released.is_empty() {
+                    println!("no stale claims");
+                    return Ok(());
+                }
+                let message = format!("Release {} stale claim(s)", released.len());
+                save(&s, &message)?;
+                if has_remote {
+                    sync::push_with_retry(&primary, &message, |_| Ok(()))?;
+                }
+                for (id, who) in &released {
+                    println!("released {id} (was {who})");
+                }
+                return Ok(());
             }
-
-            if git::fetch().is_err() || !git::remote_branch_exists() {
-                // Remote doesn't have the branch yet — just push
-                git::push().map_err(|e| format!("push failed: {e}"))?;
-                println!("pushed litebrite branch to remote");
+            let mut claimed: Vec<&model::Item> = s
+                .items
+                .values()
+                .filter(|i| i.claimed_by.is_some() && i.status == Status::Open)
+                .filter(|i| !stale || store::is_claim_stale(i, now))
+                .collect();
+            if claimed.is_empty() {
+                println!(
+                    "{}",
+                    if stale {
+                        "no stale claims"
+                    } else {
+                        "no claims"
+                    }
+                );
                 return Ok(());
             }
+            claimed.sort_by_key(|i| (i.claimed_at, i.id.clone()));
+            println!(
+                "{:<10} {:<16} {:<10} {:<16} TITLE",
+                "ID", "CLAIMED BY", "AGE", "LEASE"
+            );
+            println!("{}", "-".repeat(70));
+            for item in claimed {
+                let age = item
+                    .claimed_at
+                    .map(|at| store::format_duration((now - at).num_seconds()))
+                    .unwrap_or_else(|| "?".to_string());
+                println!(
+                    "{:<10} {:<16} {:<10} {:<16} {}",
+                    item.id,
+                    item.claimed_by.as_deref().unwrap_or("?"),
+                    age,
+                    lease_status(item, now),
+                    item.title
+                );
+            }
+            Ok(())
+        }
+        Cmd::Sync { remote } => {
+            let remotes = match remote {
+                Some(remote) => vec![remote],
+                None => sync::remotes(),
+            };
+            if let Some(missing) = remotes.iter().find(|r| !git::has_remote(r)) {
+                return Err(if remotes.len() == 1 && remotes[0] == "origin" {
+                    "no remote configured — nothing to sync".to_string()
+                } else {
+                    format!("remote {missing} does not exist in this repository")
+                });
+            }
 
-            let local_json = git::read_store()?;
-            let remote_json = git::read_store_from_ref("refs/remotes/origin/litebrite")?;
-
-            let local_ref = git::local_ref()?;
-            let remote_ref = git::remote_ref()?;
-
-            if local_ref == remote_ref {
-                println!("already in sync");
+            for remote in &remotes {
+                let status = sync_remote(remote)?;
+                if remotes.len() > 1 {
+                    println!("{remote}: {status}");
+                } else {
+                    println!("{status}");
+                }
+            }
+            if remotes.contains(&sync::primary_remote()) {
+                report_pending_claims()?;
+            }
+            Ok(())
+        }
+        Cmd::Diff {
+            ref_a,
+            ref_b,
+            json,
+            textconv,
+        } => {
+            if let Some(path) = textconv {
+                let data = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("read {}: {e}", path.display()))?;
+                // Not a store (or an unknown schema): let git show it as-is
+                match store::from_json(&data) {
+                    Ok(s) => print!("{}", diff::textconv(&s)),
+                    Err(_) => print!("{data}"),
+                }
                 return Ok(());
             }
-
-            // Try fast-forward first
-            git::fast_forward()?;
-            let new_local_ref = git::local_ref()?;
-            if new_local_ref == remote_ref {
-                // We were just behind — fast-forwarded
-                println!("fast-forwarded to remote");
+            let (ref_a, ref_b) = match (ref_a, ref_b) {
+                (Some(a), Some(b)) => (a, b),
+                (Some(a), None) => (a, git::BRANCH.to_string()),
+                _ => (
+                    git::BRANCH.to_string(),
+                    git::tracking_ref(&sync::primary_remote()),
+                ),
+            };
+            let read =
+                |r: &str| layout::load(r).map_err(|e| format!("cannot read store at {r}: {e}"));
+            let changes = diff::diff_stores(&read(&ref_a)?, &read(&ref_b)?);
+            if json {
+                let out = serde_json::to_string_pretty(&changes).map_err(|e| e.to_string())?;
+                println!("{out}");
+            } else if changes.is_empty() {
+                println!("no changes");
+            } else {
+                print!("{}", diff::format_diff(&changes));
+            }
+            Ok(())
+        }
+        Cmd::MergeDriver { base, ours, theirs } => {
+            let read = |path: &std::path::Path| {
+                std::fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))
+            };
+            let (merged, resolved) =
+                layout::merge_file(&read(&base)?, &read(&ours)?, &read(&theirs)?)?;
+            for problem in resolved {
+                eprintln!("lb: resolved {problem} — {}", problem.fix());
+            }
+            std::fs::write(&ours, merged).map_err(|e| format!("write {}: {e}", ours.display()))
+        }
+        Cmd::Status { fetch } => {
+            let remotes: Vec<String> = sync::remotes()
+                .into_iter()
+                .filter(|r| git::has_remote(r))
+                .collect();
+            if remotes.is_empty() {
+                println!("no remote configured — all changes are local");
                 return Ok(());
             }
-
-            // We're ahead or diverged — need to merge
-            let base_commit = git::merge_base()?;
-            let base_store = match base_commit {
-                Some(ref commit) => {
-                    let json = git::read_store_from_ref(commit)?;
-                    store::from_json(&json)?
+            for remote in &remotes {
+                if fetch && let Err(e) = git::fetch(remote) {
+                    eprintln!("warning: fetch from {remote} failed: {e}");
+                }
+                if !git::remote_branch_exists(remote) {
+                    println!("{remote}: never pushed");
+                    continue;
                 }
+                match git::ahead_behind(remote)? {
+                    (0, 0) => println!("{remote}: up to date"),
+                    (ahead, behind) => println!("{remote}: {ahead} ahead, {behind} behind"),
+                }
+            }
+
+            // Local changes are measured against what the primary last saw
+            let primary = sync::primary_remote();
+            let shared = match git::merge_base(&primary)? {
+                Some(commit) => layout::load(&commit)?,
                 None => model::Store::default(),
             };
-
-            let local_store = store::from_json(&local_json)?;
-            let remote_store = store::from_json(&remote_json)?;
-            let merged = store::merge_stores(&base_store, &local_store, &remote_store)?;
-            let merged_json = store::to_json(&merged)?;
-
-            git::create_merge_commit(
-                &merged_json,
-                &local_ref,
-                &remote_ref,
-                "Sync litebrite stores",
-            )?;
-            git::push().map_err(|e| format!("push failed: {e}"))?;
-            println!("synced with remote");
+            let local = load()?;
+            let changes = store::changed_items(&shared, &local);
+            if changes.is_empty() {
+                println!("no unpushed item changes");
+            } else {
+                println!("\nItems changed locally (not on {primary}):");
+                for (id, change) in changes {
+                    let title = local
+                        .items
+                        .get(&id)
+                        .or_else(|| shared.items.get(&id))
+                        .map_or("", |i| i.title.as_str());
+                    let change = match change {
+                        store::ItemChange::Added => "added",
+                        store::ItemChange::Modified => "modified",
+                        store::ItemChange::Removed => "removed",
+                    };
+                    println!("  {change:<9}{id:<10} {title}");
+                }
+            }
+            let pending = sync::load_pending()?;
+            if !pending.is_empty() {
+                println!(
+                    "\n{} offline claim(s) queued — run `lb sync` to confirm",
+                    pending.len()
+                );
+            }
             Ok(())
         }
+        Cmd::Fsck { repair } => {
+            let mut s = load()?;
+            let problems = if repair {
+                fsck::repair(&mut s)
+            } else {
+                fsck::check(&s)
+            };
+            if problems.is_empty() {
+                println!("no problems found");
+                return Ok(());
+            }
+            for problem in &problems {
+                if repair {
+                    println!("{problem} — {}", problem.fix());
+                } else {
+                    println!("{problem}");
+                }
+            }
+            if !repair {
+                return Err(format!(
+                    "{} problem(s) found — run `lb fsck --repair` to fix them",
+                    problems.len()
+                ));
+            }
+            save(&s, &format!("Repair store: {} problem(s)", problems.len()))?;
+            println!("repaired {} problem(s)", problems.len());
+            Ok(())
+        }
+        Cmd::Migrate { dry_run } => {
+            let mut value = layout::load_value(git::BRANCH)?;
+            let from = migrate::schema_version(&value);
+            migrate::check_writable(from)?;
+            let applied = migrate::upgrade(&mut value)?;
+            if applied.is_empty() {
+                println!("store is already at schema v{from}");
+                return Ok(());
+            }
+            for migration in &applied {
+                println!("v{}: {}", migration.from, migration.description);
+            }
+            let to = model::SCHEMA_VERSION;
+            if dry_run {
+                println!("would migrate store from schema v{from} to v{to}");
+                return Ok(());
+            }
+            let s = store::from_value(value)?;
+            save(&s, &format!("Migrate store from schema v{from} to v{to}"))?;
+            println!("migrated store from schema v{from} to v{to}");
+            Ok(())
+        }
+        Cmd::Config { key, value } => match (key, value) {
+            (None, _) => {
+                let s = load()?;
+                for (k, v) in store::config_entries(&s) {
+                    println!("{k} = {v}");
+                }
+                Ok(())
+            }
+            (Some(key), None) => {
+                let s = load()?;
+                let (_, v) = store::config_entries(&s)
+                    .into_iter()
+                    .find(|(k, _)| *k == key)
+                    .ok_or_else(|| format!("unknown config key: {key}"))?;
+                println!("{v}");
+                Ok(())
+            }
+            (Some(key), Some(value)) => {
+                let mut s = load()?;
+                store::set_config(&mut s, &key, &value)?;
+                save(&s, &format!("Set {key} = {value}"))?;
+                println!("set {key} = {value}");
+                Ok(())
+            }
+        },
         Cmd::Prime => {
             print_prime_context();
             Ok(())
         }
         Cmd::Setup { action } => match action {
             SetupCmd::Claude => setup_claude(),
+            SetupCmd::Git => setup_git(),
         },
         Cmd::Completions { shell } => {
             generate(shell, &mut Cli::command(), "lb", &mut std::io::stdout());
@@ -551,33 +1418,56 @@ fn run(cli: Cli) -> Result<(), String> {
     }
 }
 
-/// Check remote state and sync if possible. Returns true if remote is available.
-/// - No remote configured: returns Ok(false) (local-only operation)
-/// - Remote exists, branch on remote: fetches + fast-forwards, returns Ok(true)
-/// - Remote exists, no branch on remote: returns Err with instructions
-fn sync_from_remote() -> Result<bool, String> {
-    if !git::has_remote() {
-        return Ok(false);
-    }
-    match git::fetch() {
-        Ok(()) => {
-            git::fast_forward()?;
-            Ok(true)
-        }
-        Err(_) => {
-            Err("litebrite branch not found on remote — run `lb sync` to push it first".to_string())
-        }
-    }
-}
-
 fn load() -> Result<model::Store, String> {
-    let json = git::read_store()?;
-    store::from_json(&json)
+    layout::load(git::BRANCH)
 }
 
 fn save(s: &model::Store, message: &str) -> Result<(), String> {
-    let json = store::to_json(s)?;
-    git::write_store(&json, message)
+    layout::save(s, message)
+}
+
+/// Sync with one remote and describe what happened.
+fn sync_remote(remote: &str) -> Result<&'static str, String> {
+    if git::fetch(remote).is_err() || !git::remote_branch_exists(remote) {
+        // Remote doesn't have the branch yet — just push
+        git::push(remote).map_err(|e| format!("push to {remote} failed: {e}"))?;
+        return Ok("pushed litebrite branch to remote");
+    }
+
+    let message = format!("Sync litebrite stores with {remote}");
+    Ok(match sync::integrate(remote, &message)? {
+        sync::Integration::InSync => "already in sync",
+        sync::Integration::FastForwarded => "fast-forwarded to remote",
+        sync::Integration::Ahead => {
+            sync::push_with_retry(remote, &message, |_| Ok(()))?;
+            "synced with remote"
+        }
+        sync::Integration::Merged(resolved) => {
+            for problem in resolved {
+                println!("resolved {problem} — {}", problem.fix());
+            }
+            sync::push_with_retry(remote, &message, |_| Ok(()))?;
+            "synced with remote"
+        }
+    })
+}
+
+/// Confirm claims queued with `lb claim --offline` and say how each went.
+fn report_pending_claims() -> Result<(), String> {
+    for (claim, outcome) in sync::confirm_pending()? {
+        match outcome {
+            sync::PendingOutcome::Won => {
+                println!("confirmed offline claim on {} ({})", claim.id, claim.actor)
+            }
+            sync::PendingOutcome::Lost(reason) => {
+                println!("lost offline claim on {}: {reason}", claim.id)
+            }
+            sync::PendingOutcome::StillPending(reason) => {
+                println!("offline claim on {} still pending: {reason}", claim.id)
+            }
+        }
+    }
+    Ok(())
 }
 
 fn print_prime_context() {
@@ -595,10 +1485,16 @@ fn print_prime_context() {
         .filter(|i| i.claimed_by.is_some() && i.status == Status::Open)
         .collect();
     if !claimed.is_empty() {
+        let now = chrono::Utc::now();
         println!("\n## Claimed");
         for item in &claimed {
+            let stale = if store::is_claim_stale(item, now) {
+                ", lease expired"
+            } else {
+                ""
+            };
             println!(
-                "- {} P{} [{}] {} (by {})",
+                "- {} P{} [{}] {} (by {}{stale})",
                 item.id,
                 item.priority,
                 item.item_type,
@@ -608,6 +1504,26 @@ fn print_prime_context() {
         }
     }
 
+    // Overdue section
+    let today = due::today();
+    let mut overdue: Vec<&model::Item> =
+        s.items.values().filter(|i| is_overdue(i, today)).collect();
+    if !overdue.is_empty() {
+        overdue.sort_by_key(|i| (i.due, i.priority));
+        println!("\n## Overdue");
+        for item in &overdue {
+            let due = item.due.expect("overdue items have a due date");
+            println!(
+                "- {} P{} [{}] {} (due {due}, {})",
+                item.id,
+                item.priority,
+                item.item_type,
+                item.title,
+                due::describe(due, today)
+            );
+        }
+    }
+
     // Ready section
     let ready = store::ready_items(&s);
     if !ready.is_empty() {
@@ -631,18 +1547,37 @@ fn print_prime_context() {
 6. `lb sync` — push changes to remote
 
 ## CLI Quick Reference
-- `lb create <title>` — new item (-t epic/feature/task, -p <pri>, --parent <id>, -d <desc>)
+- `lb create <title>` — new item (-t epic/feature/task, -p <pri>, -e <estimate>, --due <date>, --milestone <name>, --parent <id>, -d <desc>)
 - `lb show <id>` — item details with deps and children
-- `lb list` — all open items (--all, -t <type>, -s <status>, --tree, --parent <id>)
-- `lb update <id>` — update fields (--title, --status, -t, -p, -d, --parent)
-- `lb close <id>` — close item (clears claim)
+- `lb list` — all open items (--all, -t <type>, -s <status>, --claimed-by <who>, --overdue, --due-before <date>, --milestone <name>, --tree, --parent <id>)
+- `lb graph [<root-id>] [--format dot|mermaid]` — dependency graph for docs (takes list filters)
+- `lb plan <epic-id> [--json]` — parallel waves and critical path for the open work under an item
+- `lb update <id>` — update fields (--title, --status, -t, -p, -e, --due, --milestone, -d, --parent)
+- `lb close <id>` — close item (clears claim; --cascade closes open descendants too)
 - `lb delete <id>` — delete item and deps
 - `lb dep add <id> --blocks <id>` — add blocking dep
 - `lb dep rm <from> <to>` — remove dep
-- `lb ready` — open + unblocked + unclaimed by priority
-- `lb claim <id>` — claim item (fetch + push)
-- `lb unclaim <id>` — release claim (fetch + push)
-- `lb sync` — sync with remote (fetch + merge + push)
+- `lb ready` — open + unblocked + unclaimed, best score first (--mine: your unblocked claims, --explain: score breakdown, --milestone <name>)
+- `--as <who>` / `LB_ACTOR` — act as a specific identity (LB_SESSION appends a session ID)
+- `lb why <id>` — every reason an item isn't ready (claim, blocking chain, missing blockers)
+- `lb blocked` — blocked items with the root blockers to work on first
+- `lb next [-t type] [--parent id]` — claim the best ready item, moving on if someone else wins it
+- `lb milestone create|list|show|close` — milestones (--target <date>); show reports progress and outside blockers
+- `lb progress <id>` — closed/claimed/blocked roll-up under an item, child by child
+- `lb unblocks <id>` — what closing an item would unblock, directly and in turn (`lb ready --unblocks` ranks by it)
+- `lb claim <id>` — claim item (fetch + push; --ttl 4h for a lease, --renew to extend it)
+- `lb claims` — list claims with lease status (--stale, --stale --release)
+- `lb claim --offline <id>` — queue a claim without network; `lb sync` confirms or reports it lost
+- `lb unclaim <id>` — release your claim (fetch + push; --force [--reason] for someone else's)
+- `lb claim --steal <id> [--reason <why>]` — take over someone else's claim (recorded in history)
+- `lb handoff <id> <who> [--reason <why>]` — hand your claim to someone else (--force for others' claims)
+- `lb sync [--remote <name>]` — sync with remotes (fetch + merge + push, confirms queued offline claims)
+- `lb diff [<ref-a> [<ref-b>]] [--json]` — field-level store changes (default: local vs remote)
+- `lb setup git` — let plain git merge/diff the store via `lb merge-driver` (per clone)
+- `lb status [--fetch]` — ahead/behind per remote and unpushed item changes
+- `lb fsck [--repair]` — check (and fix) dangling deps, extra parents, cycles, mismatched IDs
+- `lb migrate [--dry-run]` — upgrade an older store to the current schema
+- `lb config [<key> [<value>]]` — show/change repo settings (auto_close_parents, layout, score.* weights)
 - IDs: `lb-XXXX`, use any unique prefix
 
 ## Examples
@@ -650,6 +1585,46 @@ fn print_prime_context() {
     );
 }
 
+/// Attributes committed to the branch so git uses lb for the store files.
+const GIT_ATTRIBUTES: [&str; 4] = [
+    "store.json merge=litebrite diff=litebrite",
+    "items/*.json merge=litebrite",
+    "deps.json merge=litebrite",
+    "meta.json merge=litebrite",
+];
+
+fn setup_git() -> Result<(), String> {
+    git::config_set("merge.litebrite.name", "litebrite schema-aware store merge")?;
+    git::config_set("merge.litebrite.driver", "lb merge-driver %O %A %B")?;
+    git::config_set("diff.litebrite.textconv", "lb diff --textconv")?;
+    println!("registered merge driver and diff textconv in .git/config");
+
+    let existing = git::read_branch_file(".gitattributes").unwrap_or_default();
+    let missing: Vec<&str> = GIT_ATTRIBUTES
+        .into_iter()
+        .filter(|attr| !existing.lines().any(|l| l.trim() == *attr))
+        .collect();
+    if missing.is_empty() {
+        println!(".gitattributes on the litebrite branch already set up");
+    } else {
+        let mut content = existing;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        for attr in missing {
+            content.push_str(attr);
+            content.push('\n');
+        }
+        git::write_branch_file(
+            ".gitattributes",
+            &content,
+            "Use lb to merge and diff the store",
+        )?;
+        println!("added .gitattributes to the litebrite branch (shared on next `lb sync`)");
+    }
+    Ok(())
+}
+
 fn setup_claude() -> Result<(), String> {
     setup_claude_in(std::path::Path::new("."))
 }
@@ -733,71 +1708,303 @@ fn setup_claude_in(base: &std::path::Path) -> Result<(), String> {
     Ok(())
 }
 
-fn should_show(
-    item: &model::Item,
-    all: bool,
-    item_type: Option<ItemType>,
-    status: Option<Status>,
-) -> bool {
-    if !all && status.is_none() && item.status == Status::Closed {
+/// Who is acting: `--as`, then `LB_ACTOR`, then `git config litebrite.actor`,
+/// `user.name` and `user.email`. `LB_SESSION` is appended so several agents
+/// sharing one identity stay distinguishable.
+fn resolve_actor(explicit: Option<String>) -> Result<String, String> {
+    let base = explicit
+        .or_else(|| std::env::var("LB_ACTOR").ok())
+        .filter(|a| !a.trim().is_empty())
+        .or_else(|| git::config_get("litebrite.actor"))
+        .or_else(|| git::config_get("user.name"))
+        .or_else(|| git::config_get("user.email"))
+        .ok_or("no identity configured — set LB_ACTOR, pass --as, or set git config user.name")?;
+    let session = std::env::var("LB_SESSION").ok();
+    Ok(actor_with_session(base.trim(), session.as_deref()))
+}
+
+fn actor_with_session(base: &str, session: Option<&str>) -> String {
+    match session.map(str::trim) {
+        Some(sess) if !sess.is_empty() => format!("{base}/{sess}"),
+        _ => base.to_string(),
+    }
+}
+
+fn should_show(item: &model::Item, filter: &ListFilter) -> bool {
+    if !filter.all && filter.status.is_none() && item.status == Status::Closed {
         return false;
     }
-    if let Some(t) = item_type
+    if let Some(t) = filter.item_type
         && item.item_type != t
     {
         return false;
     }
-    if let Some(s) = status
+    if let Some(s) = filter.status
         && item.status != s
     {
         return false;
     }
+    if let Some(ref who) = filter.claimed_by
+        && item.claimed_by.as_deref() != Some(who.as_str())
+    {
+        return false;
+    }
+    if filter.overdue && !is_overdue(item, due::today()) {
+        return false;
+    }
+    if let Some(before) = filter.due_before
+        && item.due.is_none_or(|d| d >= before)
+    {
+        return false;
+    }
+    if let Some(ref name) = filter.milestone
+        && item.milestone.as_deref() != Some(name.as_str())
+    {
+        return false;
+    }
     true
 }
 
+fn is_overdue(item: &model::Item, today: chrono::NaiveDate) -> bool {
+    item.status == Status::Open && item.due.is_some_and(|d| d < today)
+}
+
+/// One line per unclosed blocker, nested under what it blocks.
+fn print_blocker(s: &model::Store, blocker: &why::Blocker, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match blocker {
+        why::Blocker::Open { id, blockers } => {
+            let item = &s.items[id];
+            let claim = match store::active_claimant(item, chrono::Utc::now()) {
+                Some(who) => format!(", claimed by {who}"),
+                None => String::new(),
+            };
+            println!(
+                "{indent}blocked by {id} [{}{claim}] {}",
+                item.status, item.title
+            );
+            for b in blockers {
+                print_blocker(s, b, depth + 1);
+            }
+        }
+        why::Blocker::Seen(id) => println!("{indent}blocked by {id} (see above)"),
+        why::Blocker::Missing(id) => println!("{indent}blocked by {id} (no such item)"),
+    }
+}
+
+fn format_event(event: &model::Event) -> String {
+    let mut line = format!(
+        "{} {} by {}",
+        event.at.format("%Y-%m-%d %H:%M"),
+        event.kind,
+        event.actor
+    );
+    match (&event.from, &event.to) {
+        (Some(from), Some(to)) => line.push_str(&format!(": {from} -> {to}")),
+        (Some(from), None) => line.push_str(&format!(": released {from}")),
+        (None, Some(to)) => line.push_str(&format!(": -> {to}")),
+        (None, None) => {}
+    }
+    if let Some(ref reason) = event.reason {
+        line.push_str(&format!(" ({reason})"));
+    }
+    line
+}
+
+/// Human description of a claim lease: "no expiry", "expires in 3h", "expired 2d ago".
+fn lease_status(item: &model::Item, now: chrono::DateTime<chrono::Utc>) -> String {
+    match store::claim_expires_at(item) {
+        None => "no expiry".to_string(),
+        Some(exp) if exp <= now => {
+            format!(
+                "expired {} ago",
+                store::format_duration((now - exp).num_seconds())
+            )
+        }
+        Some(exp) => format!(
+            "expires in {}",
+            store::format_duration((exp - now).num_seconds())
+        ),
+    }
+}
+
+/// The full `lb show` view of one item: fields, history and deps.
+fn print_item_details(s: &model::Store, id: &str) -> Result<(), String> {
+    let item = s.items.get(id).ok_or("item not found")?;
+    println!("  ID: {}", item.id);
+    println!("  Title: {}", item.title);
+    println!("  Type: {}", item.item_type);
+    println!("  Status: {}", item.status);
+    println!("  Priority: P{}", item.priority);
+    let with_children = progress::subtree(s, id, chrono::Utc::now()).estimated;
+    match item.estimate {
+        Some(own) if with_children.weight() > own.weight() => {
+            println!("  Estimate: {own} ({with_children} with descendants)")
+        }
+        Some(own) => println!("  Estimate: {own}"),
+        None if !with_children.is_empty() => {
+            println!("  Estimate: {with_children} (descendants)")
+        }
+        None => {}
+    }
+    if let Some(ref name) = item.milestone {
+        match s.milestones.get(name) {
+            Some(m) => match m.target {
+                Some(target) => println!("  Milestone: {name} ({}, target {target})", m.state),
+                None => println!("  Milestone: {name} ({})", m.state),
+            },
+            None => println!("  Milestone: {name} (unknown)"),
+        }
+    }
+    if let Some(deadline) = due::deadline(s, id) {
+        let when = due::describe(deadline.date, due::today());
+        match deadline.from {
+            None => println!("  Due: {} ({when})", deadline.date),
+            Some(from) => println!("  Due: {} (from {from}, {when})", deadline.date),
+        }
+    }
+    if let Some(ref who) = item.claimed_by {
+        let now = chrono::Utc::now();
+        match item.claimed_at {
+            Some(at) => println!(
+                "  Claimed by: {who} (since {}, {})",
+                at.format("%Y-%m-%d %H:%M"),
+                lease_status(item, now)
+            ),
+            None => println!("  Claimed by: {who}"),
+        }
+    } else if let Some(pending) = sync::pending_claim(&item.id) {
+        println!(
+            "  Claimed by: {} (tentative — queued offline, confirm with `lb sync`)",
+            pending.actor
+        );
+    }
+    if let Some(ref desc) = item.description {
+        println!("  Description: {desc}");
+    }
+    println!("  Created: {}", item.created_at.format("%Y-%m-%d %H:%M"));
+    println!("  Updated: {}", item.updated_at.format("%Y-%m-%d %H:%M"));
+    if !item.history.is_empty() {
+        println!("  History:");
+        for event in &item.history {
+            println!("    {}", format_event(event));
+        }
+    }
+
+    if let Some(pid) = store::get_parent(s, id)
+        && let Some(p) = s.items.get(&pid)
+    {
+        println!("  Parent: {} ({})", pid, p.title);
+    }
+
+    let children = store::get_children(s, id);
+    if !children.is_empty() {
+        println!("  Children:");
+        for cid in &children {
+            if let Some(c) = s.items.get(cid) {
+                println!("    {} [{}] {}", cid, c.status, c.title);
+            }
+        }
+        println!(
+            "  Progress: {}",
+            progress::rollup(s, id, chrono::Utc::now())
+        );
+    }
+
+    let blockers = store::get_blockers(s, id);
+    if !blockers.is_empty() {
+        println!("  Blocked by:");
+        for bid in &blockers {
+            if let Some(b) = s.items.get(bid) {
+                println!("    {} [{}] {}", bid, b.status, b.title);
+            }
+        }
+    }
+
+    let blocking = store::get_blocking(s, id);
+    if !blocking.is_empty() {
+        println!("  Blocks:");
+        for bid in &blocking {
+            if let Some(b) = s.items.get(bid) {
+                println!("    {} [{}] {}", bid, b.status, b.title);
+            }
+        }
+    }
+    Ok(())
+}
+
 fn print_list_header() {
     println!(
-        "{:<10} {:<8} {:<14} {:<4} TITLE",
-        "ID", "TYPE", "STATUS", "PRI"
+        "{:<10} {:<8} {:<14} {:<4} {:<12} TITLE",
+        "ID", "TYPE", "STATUS", "PRI", "DUE"
     );
-    println!("{}", "-".repeat(60));
+    println!("{}", "-".repeat(73));
 }
 
-fn print_list_row(item: &model::Item) {
-    let status_str = if item.claimed_by.is_some() {
-        "open (claimed)".to_string()
-    } else {
-        item.status.to_string()
-    };
+fn print_list_row(s: &model::Store, item: &model::Item) {
     println!(
-        "{:<10} {:<8} {:<14} {:<4} {}",
+        "{:<10} {:<8} {:<14} {:<4} {:<12} {}",
         item.id,
         item.item_type,
-        status_str,
+        status_label(item),
         format!("P{}", item.priority),
+        due_label(s, item),
         item.title
     );
 }
 
-fn print_tree_item(
-    store: &model::Store,
-    id: &str,
-    depth: usize,
-    all: bool,
-    item_type: Option<ItemType>,
-    status: Option<Status>,
-) {
+/// Deadline as shown in listings; one inherited from a parent is in
+/// parentheses.
+fn due_label(s: &model::Store, item: &model::Item) -> String {
+    if item.status == Status::Closed {
+        return item.due.map(|d| d.to_string()).unwrap_or_default();
+    }
+    match due::deadline(s, &item.id) {
+        Some(due::Deadline { date, from: None }) => date.to_string(),
+        Some(due::Deadline { date, .. }) => format!("({date})"),
+        None => String::new(),
+    }
+}
+
+fn parse_due_arg(s: &str) -> Result<chrono::NaiveDate, String> {
+    due::parse(s, due::today())
+}
+
+/// Status as shown in listings, noting claims that are active, stale or
+/// still waiting to be pushed.
+fn status_label(item: &model::Item) -> String {
+    if store::is_claim_stale(item, chrono::Utc::now()) {
+        "open (stale)".to_string()
+    } else if item.claimed_by.is_some() {
+        "open (claimed)".to_string()
+    } else if item.status == Status::Open && sync::pending_claim(&item.id).is_some() {
+        "open (pending)".to_string()
+    } else {
+        item.status.to_string()
+    }
+}
+
+fn print_tree_item(store: &model::Store, id: &str, depth: usize, filter: &ListFilter) {
     if let Some(item) = store.items.get(id) {
-        let visible = should_show(item, all, item_type, status);
+        let visible = should_show(item, filter);
         let child_depth = if visible {
-            let claimed = if item.claimed_by.is_some() {
+            let claimed = if store::is_claim_stale(item, chrono::Utc::now()) {
+                " *stale claim*"
+            } else if item.claimed_by.is_some() {
                 " *claimed*"
+            } else if item.status == Status::Open && sync::pending_claim(&item.id).is_some() {
+                " *pending claim*"
             } else {
                 ""
             };
             let indent = "  ".repeat(depth);
+            let rollup = if store::get_children(store, id).is_empty() {
+                String::new()
+            } else {
+                format!(" — {}", progress::rollup(store, id, chrono::Utc::now()))
+            };
             println!(
-                "{}{} [{}] P{} {} ({}){claimed}",
+                "{}{} [{}] P{} {} ({}){claimed}{rollup}",
                 indent, item.id, item.status, item.priority, item.title, item.item_type
             );
             depth + 1
@@ -806,7 +2013,7 @@ fn print_tree_item(
         };
         let children = store::get_children(store, id);
         for cid in &children {
-            print_tree_item(store, cid, child_depth, all, item_type, status);
+            print_tree_item(store, cid, child_depth, filter);
         }
     }
 }
@@ -814,21 +2021,14 @@ fn print_tree_item(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
     use std::process::Command;
 
     fn make_item(status: Status, item_type: ItemType) -> model::Item {
-        let now = Utc::now();
         model::Item {
-            id: "lb-test".to_string(),
             title: "test".to_string(),
-            description: None,
             item_type,
             status,
-            priority: 2,
-            claimed_by: None,
-            created_at: now,
-            updated_at: now,
+            ..model::Item::for_test("lb-test")
         }
     }
 
@@ -837,34 +2037,81 @@ mod tests {
     #[test]
     fn hides_closed_by_default() {
         let item = make_item(Status::Closed, ItemType::Task);
-        assert!(!should_show(&item, false, None, None));
+        assert!(!should_show(&item, &ListFilter::default()));
     }
 
     #[test]
     fn shows_closed_with_all() {
         let item = make_item(Status::Closed, ItemType::Task);
-        assert!(should_show(&item, true, None, None));
+        let filter = ListFilter {
+            all: true,
+            ..Default::default()
+        };
+        assert!(should_show(&item, &filter));
     }
 
     #[test]
     fn filters_by_item_type() {
         let item = make_item(Status::Open, ItemType::Epic);
-        assert!(!should_show(&item, false, Some(ItemType::Task), None));
-        assert!(should_show(&item, false, Some(ItemType::Epic), None));
+        let task = ListFilter {
+            item_type: Some(ItemType::Task),
+            ..Default::default()
+        };
+        let epic = ListFilter {
+            item_type: Some(ItemType::Epic),
+            ..Default::default()
+        };
+        assert!(!should_show(&item, &task));
+        assert!(should_show(&item, &epic));
     }
 
     #[test]
     fn filters_by_status() {
         let item = make_item(Status::Open, ItemType::Task);
-        assert!(should_show(&item, false, None, Some(Status::Open)));
-        assert!(!should_show(&item, false, None, Some(Status::Closed)));
+        let open = ListFilter {
+            status: Some(Status::Open),
+            ..Default::default()
+        };
+        let closed = ListFilter {
+            status: Some(Status::Closed),
+            ..Default::default()
+        };
+        assert!(should_show(&item, &open));
+        assert!(!should_show(&item, &closed));
     }
 
     #[test]
     fn status_filter_overrides_closed_hiding() {
         let item = make_item(Status::Closed, ItemType::Task);
         // With status filter for Closed, should show even without --all
-        assert!(should_show(&item, false, None, Some(Status::Closed)));
+        let closed = ListFilter {
+            status: Some(Status::Closed),
+            ..Default::default()
+        };
+        assert!(should_show(&item, &closed));
+    }
+
+    #[test]
+    fn filters_by_claimant() {
+        let mut item = make_item(Status::Open, ItemType::Task);
+        let alice = ListFilter {
+            claimed_by: Some("alice".to_string()),
+            ..Default::default()
+        };
+        assert!(!should_show(&item, &alice));
+        item.claimed_by = Some("alice".to_string());
+        assert!(should_show(&item, &alice));
+        item.claimed_by = Some("bob".to_string());
+        assert!(!should_show(&item, &alice));
+    }
+
+    // --- actor identity ---
+
+    #[test]
+    fn actor_session_suffix() {
+        assert_eq!(actor_with_session("alice", None), "alice");
+        assert_eq!(actor_with_session("alice", Some("")), "alice");
+        assert_eq!(actor_with_session("claude", Some("a1b2")), "claude/a1b2");
     }
 
     // --- CLI integration ---
@@ -1215,7 +2462,7 @@ mod tests {
             .filter(|g| {
                 g.get("hooks")
                     .and_then(|h| h.as_array())
-                    .map_or(false, |hooks| {
+                    .is_some_and(|hooks| {
                         hooks
                             .iter()
                             .any(|h| h.get("command").and_then(|c| c.as_str()) == Some("lb prime"))
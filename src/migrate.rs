@@ -0,0 +1,130 @@
+//! Schema migrations. Each migration upgrades the raw JSON of a whole store
+//! by one version before it is deserialized, so old stores are upgraded in
+//! memory on every read and written back in the current schema.
+
+use crate::model::SCHEMA_VERSION;
+use serde_json::Value;
+
+/// Version of stores written before `schema_version` existed.
+pub const UNVERSIONED: u32 = 1;
+
+pub struct Migration {
+    /// Version this migration upgrades from (to `from + 1`).
+    pub from: u32,
+    pub description: &'static str,
+    apply: fn(&mut Value) -> Result<(), String>,
+}
+
+/// Every migration, in order. `MIGRATIONS.last().from + 1 == SCHEMA_VERSION`.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "rewrite legacy statuses (in_progress, blocked, deferred) as open",
+    apply: legacy_statuses,
+}];
+
+/// Schema version of a raw store.
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(UNVERSIONED, |v| v as u32)
+}
+
+/// Migrations needed to bring a store at `version` up to date.
+pub fn pending(version: u32) -> impl Iterator<Item = &'static Migration> {
+    MIGRATIONS.iter().filter(move |m| m.from >= version)
+}
+
+/// Upgrade a raw store to `SCHEMA_VERSION` in place, returning the
+/// migrations applied. Stores from a newer `lb` are left untouched.
+pub fn upgrade(value: &mut Value) -> Result<Vec<&'static Migration>, String> {
+    let version = schema_version(value);
+    if version >= SCHEMA_VERSION {
+        return Ok(Vec::new());
+    }
+    let mut applied = Vec::new();
+    for migration in pending(version) {
+        (migration.apply)(value).map_err(|e| {
+            format!(
+                "migrating store from schema v{} failed: {e}",
+                migration.from
+            )
+        })?;
+        applied.push(migration);
+    }
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+    }
+    Ok(applied)
+}
+
+/// Error for writing a store created by a newer `lb`, which would drop
+/// whatever that version added.
+pub fn check_writable(version: u32) -> Result<(), String> {
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "store uses schema v{version} but this lb only knows up to v{SCHEMA_VERSION} — upgrade lb to make changes"
+        ));
+    }
+    Ok(())
+}
+
+fn legacy_statuses(value: &mut Value) -> Result<(), String> {
+    let Some(items) = value.get_mut("items").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+    for item in items.values_mut() {
+        if let Some(status) = item.get_mut("status")
+            && matches!(
+                status.as_str(),
+                Some("in_progress" | "blocked" | "deferred")
+            )
+        {
+            *status = Value::from("open");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn migrations_form_a_chain() {
+        for (i, m) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(m.from, UNVERSIONED + i as u32);
+        }
+        let last = MIGRATIONS.last().map_or(UNVERSIONED, |m| m.from + 1);
+        assert_eq!(last, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn upgrades_unversioned_store() {
+        let mut store = json!({
+            "items": {
+                "lb-aaaa": { "status": "in_progress" },
+                "lb-bbbb": { "status": "closed" }
+            },
+            "deps": []
+        });
+        let applied = upgrade(&mut store).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(store["schema_version"], SCHEMA_VERSION);
+        assert_eq!(store["items"]["lb-aaaa"]["status"], "open");
+        assert_eq!(store["items"]["lb-bbbb"]["status"], "closed");
+        // Already current: nothing to do
+        assert!(upgrade(&mut store).unwrap().is_empty());
+    }
+
+    #[test]
+    fn newer_stores_are_left_alone_and_not_writable() {
+        let mut store = json!({ "schema_version": SCHEMA_VERSION + 1, "items": {}, "deps": [] });
+        let before = store.clone();
+        assert!(upgrade(&mut store).unwrap().is_empty());
+        assert_eq!(store, before);
+        assert!(check_writable(SCHEMA_VERSION + 1).is_err());
+        assert!(check_writable(SCHEMA_VERSION).is_ok());
+    }
+}
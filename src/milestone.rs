@@ -0,0 +1,210 @@
+//! Milestones: named targets such as releases that items are scheduled for,
+//! and reports on how close each is.
+
+use crate::model::{Extra, Milestone, MilestoneState, Status, Store};
+use crate::store;
+use chrono::{NaiveDate, Utc};
+use std::collections::BTreeMap;
+
+pub fn create(store: &mut Store, name: &str, target: Option<NaiveDate>) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!(
+            "invalid milestone name: '{name}' (no spaces, e.g. v1.2)"
+        ));
+    }
+    if store.milestones.contains_key(name) {
+        return Err(format!("milestone '{name}' already exists"));
+    }
+    store.milestones.insert(
+        name.to_string(),
+        Milestone {
+            name: name.to_string(),
+            target,
+            state: MilestoneState::Open,
+            created_at: Utc::now(),
+            extra: Extra::new(),
+        },
+    );
+    Ok(())
+}
+
+pub fn get<'a>(store: &'a Store, name: &str) -> Result<&'a Milestone, String> {
+    store
+        .milestones
+        .get(name)
+        .ok_or_else(|| format!("no milestone named '{name}'"))
+}
+
+/// Close a milestone. Items still open in it stay scheduled for it.
+pub fn close(store: &mut Store, name: &str) -> Result<(), String> {
+    let milestone = store
+        .milestones
+        .get_mut(name)
+        .ok_or_else(|| format!("no milestone named '{name}'"))?;
+    if milestone.state == MilestoneState::Closed {
+        return Err(format!("milestone '{name}' is already closed"));
+    }
+    milestone.state = MilestoneState::Closed;
+    Ok(())
+}
+
+/// Only open milestones take new items.
+pub fn check_open(store: &Store, name: &str) -> Result<(), String> {
+    if get(store, name)?.state == MilestoneState::Closed {
+        return Err(format!("milestone '{name}' is closed"));
+    }
+    Ok(())
+}
+
+/// Schedule `id` for a milestone, or unschedule it with `None`.
+pub fn assign(store: &mut Store, id: &str, name: Option<&str>) -> Result<(), String> {
+    if let Some(name) = name {
+        check_open(store, name)?;
+    }
+    let item = store.items.get_mut(id).ok_or("item not found")?;
+    item.milestone = name.map(str::to_string);
+    Ok(())
+}
+
+/// Where a milestone stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Items scheduled for it, in ID order.
+    pub items: Vec<String>,
+    pub open: usize,
+    pub closed: usize,
+    /// Open items held up by unclosed blockers that aren't scheduled for the
+    /// milestone, with those blockers.
+    pub blocked_outside: BTreeMap<String, Vec<String>>,
+}
+
+pub fn report(store: &Store, name: &str) -> Report {
+    let in_milestone = |id: &str| {
+        store
+            .items
+            .get(id)
+            .is_some_and(|i| i.milestone.as_deref() == Some(name))
+    };
+    let mut report = Report {
+        items: Vec::new(),
+        open: 0,
+        closed: 0,
+        blocked_outside: BTreeMap::new(),
+    };
+    for item in store
+        .items
+        .values()
+        .filter(|i| i.milestone.as_deref() == Some(name))
+    {
+        report.items.push(item.id.clone());
+        if item.status == Status::Closed {
+            report.closed += 1;
+            continue;
+        }
+        report.open += 1;
+        let outside: Vec<String> = store::get_blockers(store, &item.id)
+            .into_iter()
+            .filter(|b| !in_milestone(b))
+            .filter(|b| {
+                store
+                    .items
+                    .get(b)
+                    .is_none_or(|i| i.status != Status::Closed)
+            })
+            .collect();
+        if !outside.is_empty() {
+            report.blocked_outside.insert(item.id.clone(), outside);
+        }
+    }
+    report
+}
+
+/// Item counts for every milestone: `(open, closed)` by name.
+pub fn counts(store: &Store) -> BTreeMap<&str, (usize, usize)> {
+    let mut counts: BTreeMap<&str, (usize, usize)> = store
+        .milestones
+        .keys()
+        .map(|name| (name.as_str(), (0, 0)))
+        .collect();
+    for item in store.items.values() {
+        if let Some(name) = item.milestone.as_deref()
+            && let Some((open, closed)) = counts.get_mut(name)
+        {
+            match item.status {
+                Status::Open => *open += 1,
+                Status::Closed => *closed += 1,
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Dep, DepType, Item};
+
+    fn add(store: &mut Store, id: &str, milestone: Option<&str>) {
+        store.items.insert(
+            id.to_string(),
+            Item {
+                milestone: milestone.map(str::to_string),
+                ..Item::for_test(id)
+            },
+        );
+    }
+
+    #[test]
+    fn create_close_and_assign() {
+        let mut s = Store::default();
+        add(&mut s, "lb-a", None);
+        create(&mut s, "v1", None).unwrap();
+        assert!(
+            create(&mut s, "v1", None)
+                .unwrap_err()
+                .contains("already exists")
+        );
+        assert!(create(&mut s, "v 2", None).is_err());
+
+        assign(&mut s, "lb-a", Some("v1")).unwrap();
+        assert_eq!(s.items["lb-a"].milestone.as_deref(), Some("v1"));
+        assert!(assign(&mut s, "lb-a", Some("v9")).is_err());
+
+        close(&mut s, "v1").unwrap();
+        assert!(close(&mut s, "v1").unwrap_err().contains("already closed"));
+        assert!(
+            assign(&mut s, "lb-a", Some("v1"))
+                .unwrap_err()
+                .contains("closed")
+        );
+        assign(&mut s, "lb-a", None).unwrap();
+        assert_eq!(s.items["lb-a"].milestone, None);
+    }
+
+    #[test]
+    fn report_flags_blockers_outside_the_milestone() {
+        // lb-a (done) and lb-b, lb-c are in v1; lb-x (outside) blocks lb-b,
+        // lb-a blocks lb-c, lb-gone blocks lb-c
+        let mut s = Store::default();
+        create(&mut s, "v1", None).unwrap();
+        create(&mut s, "v2", None).unwrap();
+        add(&mut s, "lb-a", Some("v1"));
+        add(&mut s, "lb-b", Some("v1"));
+        add(&mut s, "lb-c", Some("v1"));
+        add(&mut s, "lb-x", Some("v2"));
+        s.items.get_mut("lb-a").unwrap().status = Status::Closed;
+        for (from, to) in [("lb-x", "lb-b"), ("lb-a", "lb-c"), ("lb-gone", "lb-c")] {
+            s.add_dep(Dep::new(from.into(), to.into(), DepType::Blocks));
+        }
+
+        let r = report(&s, "v1");
+        assert_eq!(r.items, ["lb-a", "lb-b", "lb-c"]);
+        assert_eq!((r.open, r.closed), (2, 1));
+        assert_eq!(r.blocked_outside["lb-b"], ["lb-x"]);
+        assert_eq!(r.blocked_outside["lb-c"], ["lb-gone"]);
+
+        assert_eq!(counts(&s)["v1"], (2, 1));
+        assert_eq!(counts(&s)["v2"], (1, 0));
+    }
+}
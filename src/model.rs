@@ -1,7 +1,16 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::sync::OnceLock;
+
+/// Schema version written by this build. Bump it together with a new entry
+/// in `migrate::MIGRATIONS`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Fields this build doesn't know about, kept so that round-tripping a store
+/// written by a newer `lb` doesn't drop them.
+pub type Extra = BTreeMap<String, serde_json::Value>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -91,7 +100,7 @@ impl fmt::Display for DepType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Item {
     pub id: String,
     pub title: String,
@@ -100,23 +109,498 @@ pub struct Item {
     pub item_type: ItemType,
     pub status: Status,
     pub priority: u8,
+    /// Expected size of the item itself, not counting its children.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Estimate>,
+    /// Date the item should be closed by; see `due`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    /// Name of the milestone the item is scheduled for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub claimed_by: Option<String>,
+    /// Start of the current claim lease (set by claim, reset by renew).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_at: Option<DateTime<Utc>>,
+    /// Lease length in seconds. `None` means the claim never expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_ttl: Option<u64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Audit trail of claim changes made on someone else's behalf.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Event>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// How big an item is: abstract points, or a duration of work.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Estimate {
+    Points(f64),
+    /// Seconds of work.
+    Duration(u64),
+}
+
+impl Estimate {
+    /// A single number for weighing items against each other: points as
+    /// they are, durations in hours.
+    pub fn weight(&self) -> f64 {
+        match *self {
+            Estimate::Points(p) => p,
+            Estimate::Duration(secs) => secs as f64 / 3600.0,
+        }
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Estimate::Points(p) => write!(f, "{p}pt"),
+            Estimate::Duration(secs) => {
+                write!(f, "{}", crate::store::format_duration(secs as i64))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Estimate {
+    type Err = String;
+    /// `3`, `3pt` or `0.5pts` are points; `30m`, `4h`, `2d` are durations.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let num = s.trim_end_matches("pts").trim_end_matches("pt");
+        if let Ok(p) = num.parse::<f64>() {
+            return if p.is_finite() && p >= 0.0 {
+                Ok(Estimate::Points(p))
+            } else {
+                Err(format!("invalid estimate: {s}"))
+            };
+        }
+        crate::store::parse_duration(s)
+            .map(Estimate::Duration)
+            .map_err(|_| format!("invalid estimate: {s} (e.g. 3, 5pt, 4h, 2d)"))
+    }
+}
+
+/// Estimates added together, keeping points and durations apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EstimateSum {
+    pub points: f64,
+    pub seconds: u64,
+}
+
+impl EstimateSum {
+    pub fn add(&mut self, estimate: Estimate) {
+        match estimate {
+            Estimate::Points(p) => self.points += p,
+            Estimate::Duration(secs) => self.seconds += secs,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points == 0.0 && self.seconds == 0
+    }
+
+    /// Same scale as [`Estimate::weight`].
+    pub fn weight(&self) -> f64 {
+        self.points + self.seconds as f64 / 3600.0
+    }
+}
+
+impl fmt::Display for EstimateSum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.points != 0.0, self.seconds != 0) {
+            (true, true) => write!(
+                f,
+                "{} + {}",
+                Estimate::Points(self.points),
+                Estimate::Duration(self.seconds)
+            ),
+            (_, true) => write!(f, "{}", Estimate::Duration(self.seconds)),
+            _ => write!(f, "{}", Estimate::Points(self.points)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// Claim taken from another claimant.
+    Steal,
+    /// Claim reassigned to a named claimant.
+    Handoff,
+    /// Someone else's claim released with `--force`.
+    ForceUnclaim,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventKind::Steal => write!(f, "steal"),
+            EventKind::Handoff => write!(f, "handoff"),
+            EventKind::ForceUnclaim => write!(f, "force-unclaim"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    pub kind: EventKind,
+    /// Claimant before the change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Claimant after the change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+impl Item {
+    /// An open P2 task titled after its ID, for tests to adjust with
+    /// struct-update syntax.
+    #[cfg(test)]
+    pub fn for_test(id: &str) -> Item {
+        let now = Utc::now();
+        Item {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: None,
+            item_type: ItemType::Task,
+            status: Status::Open,
+            priority: 2,
+            estimate: None,
+            due: None,
+            milestone: None,
+            claimed_by: None,
+            claimed_at: None,
+            claim_ttl: None,
+            created_at: now,
+            updated_at: now,
+            history: Vec::new(),
+            extra: Extra::new(),
+        }
+    }
+}
+
+/// An edge between two items. Two deps are equal when they join the same
+/// items the same way, whenever they were added.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dep {
     pub from_id: String,
     pub to_id: String,
     pub dep_type: DepType,
+    /// When the dep was added, used to settle conflicting deps after a
+    /// merge. Missing on deps added before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl Dep {
+    pub fn new(from_id: String, to_id: String, dep_type: DepType) -> Self {
+        Dep {
+            from_id,
+            to_id,
+            dep_type,
+            created_at: Some(Utc::now()),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+impl PartialEq for Dep {
+    fn eq(&self, other: &Self) -> bool {
+        (&self.from_id, &self.to_id, self.dep_type)
+            == (&other.from_id, &other.to_id, other.dep_type)
+    }
+}
+
+impl Eq for Dep {}
+
+impl std::hash::Hash for Dep {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (&self.from_id, &self.to_id, self.dep_type).hash(state);
+    }
+}
+
+/// How the store is laid out in the branch's tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// Everything in one `store.json`.
+    #[default]
+    SingleFile,
+    /// One `items/<id>.json` per item, plus `deps.json` and `meta.json`.
+    PerItem,
+}
+
+impl Layout {
+    pub fn is_single_file(&self) -> bool {
+        *self == Layout::SingleFile
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layout::SingleFile => write!(f, "single-file"),
+            Layout::PerItem => write!(f, "per-item"),
+        }
+    }
+}
+
+impl std::str::FromStr for Layout {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "single-file" => Ok(Layout::SingleFile),
+            "per-item" => Ok(Layout::PerItem),
+            _ => Err(format!(
+                "unknown layout: {s} (valid: single-file, per-item)"
+            )),
+        }
+    }
+}
+
+/// How much each factor counts towards an item's `lb ready` score; see
+/// `score`. Zero turns a factor off, a negative weight inverts it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    /// Per priority level above P4.
+    pub priority: f64,
+    /// Per day since the item was created.
+    pub age: f64,
+    /// Per item that closing this one would unblock.
+    pub unblocks: f64,
+    /// Per priority level of the nearest enclosing epic above P4.
+    pub parent: f64,
+    /// Per day the deadline is closer than two weeks away, or overdue.
+    pub due: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            priority: 10.0,
+            age: 0.1,
+            unblocks: 1.0,
+            parent: 2.0,
+            due: 1.0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    pub fn is_default(&self) -> bool {
+        *self == ScoreWeights::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MilestoneState {
+    #[default]
+    Open,
+    Closed,
+}
+
+impl fmt::Display for MilestoneState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MilestoneState::Open => f.pad("open"),
+            MilestoneState::Closed => f.pad("closed"),
+        }
+    }
+}
+
+/// A named target that items are scheduled for, such as a release.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Milestone {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<NaiveDate>,
+    #[serde(default)]
+    pub state: MilestoneState,
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Repository-wide policy. Lives in the store so every clone shares it.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Settings {
+    /// Close a parent automatically once its last open child closes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_close_parents: bool,
+    /// Tree layout used the next time the store is written.
+    #[serde(default, skip_serializing_if = "Layout::is_single_file")]
+    pub layout: Layout,
+    /// Weights for ranking `lb ready`.
+    #[serde(default, skip_serializing_if = "ScoreWeights::is_default")]
+    pub score: ScoreWeights,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Settings {
+    pub fn is_default(&self) -> bool {
+        *self == Settings::default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Store {
+    /// Format version; see `migrate`. Stores from before versioning lack it.
+    #[serde(default)]
+    pub schema_version: u32,
     pub items: BTreeMap<String, Item>,
-    pub deps: Vec<Dep>,
+    /// Private so every change goes through methods that keep `index` current.
+    deps: Vec<Dep>,
+    #[serde(default, skip_serializing_if = "Settings::is_default")]
+    pub settings: Settings,
+    /// Milestones by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub milestones: BTreeMap<String, Milestone>,
+    #[serde(flatten)]
+    pub extra: Extra,
+    /// Built from `deps` on first lookup, then updated as deps change.
+    #[serde(skip)]
+    index: OnceLock<DepIndex>,
+}
+
+impl Store {
+    pub fn deps(&self) -> &[Dep] {
+        &self.deps
+    }
+
+    /// Adjacency lists for the deps, so lookups don't scan every dep.
+    pub fn index(&self) -> &DepIndex {
+        self.index.get_or_init(|| DepIndex::build(&self.deps))
+    }
+
+    pub fn add_dep(&mut self, dep: Dep) {
+        if let Some(index) = self.index.get_mut() {
+            index.insert(&dep);
+        }
+        self.deps.push(dep);
+    }
+
+    /// Keep only the deps for which `keep` returns true.
+    pub fn retain_deps(&mut self, mut keep: impl FnMut(&Dep) -> bool) {
+        let mut removed = Vec::new();
+        self.deps.retain(|d| {
+            let kept = keep(d);
+            if !kept {
+                removed.push(d.clone());
+            }
+            kept
+        });
+        if let Some(index) = self.index.get_mut() {
+            for dep in &removed {
+                index.remove(dep);
+            }
+        }
+    }
+
+    pub fn set_deps(&mut self, deps: Vec<Dep>) {
+        self.deps = deps;
+        self.index = OnceLock::new();
+    }
+}
+
+type Adjacency = HashMap<String, Vec<String>>;
+
+/// Each item's neighbours along parent and blocking deps, in dep order.
+#[derive(Debug, Clone, Default)]
+pub struct DepIndex {
+    parents: Adjacency,
+    children: Adjacency,
+    blockers: Adjacency,
+    blocking: Adjacency,
+}
+
+impl DepIndex {
+    fn build(deps: &[Dep]) -> Self {
+        let mut index = DepIndex::default();
+        for dep in deps {
+            index.insert(dep);
+        }
+        index
+    }
+
+    /// Lists holding `dep.to_id` under `dep.from_id`, and the reverse.
+    fn lists(&mut self, dep_type: DepType) -> (&mut Adjacency, &mut Adjacency) {
+        match dep_type {
+            DepType::Parent => (&mut self.parents, &mut self.children),
+            DepType::Blocks => (&mut self.blocking, &mut self.blockers),
+        }
+    }
+
+    fn insert(&mut self, dep: &Dep) {
+        let (forward, backward) = self.lists(dep.dep_type);
+        forward
+            .entry(dep.from_id.clone())
+            .or_default()
+            .push(dep.to_id.clone());
+        backward
+            .entry(dep.to_id.clone())
+            .or_default()
+            .push(dep.from_id.clone());
+    }
+
+    fn remove(&mut self, dep: &Dep) {
+        fn remove_one(lists: &mut Adjacency, key: &str, value: &str) {
+            if let Some(list) = lists.get_mut(key) {
+                if let Some(pos) = list.iter().position(|v| v == value) {
+                    list.remove(pos);
+                }
+                if list.is_empty() {
+                    lists.remove(key);
+                }
+            }
+        }
+        let (forward, backward) = self.lists(dep.dep_type);
+        remove_one(forward, &dep.from_id, &dep.to_id);
+        remove_one(backward, &dep.to_id, &dep.from_id);
+    }
+
+    /// The item's parent; the first one if a bad merge left several.
+    pub fn parent(&self, id: &str) -> Option<&str> {
+        self.parents.get(id)?.first().map(String::as_str)
+    }
+
+    pub fn children(&self, id: &str) -> &[String] {
+        self.children.get(id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Items blocking this one.
+    pub fn blockers(&self, id: &str) -> &[String] {
+        self.blockers.get(id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Items this one blocks.
+    pub fn blocking(&self, id: &str) -> &[String] {
+        self.blocking.get(id).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Store {
+            schema_version: SCHEMA_VERSION,
+            items: BTreeMap::new(),
+            deps: Vec::new(),
+            settings: Settings::default(),
+            milestones: BTreeMap::new(),
+            extra: Extra::new(),
+            index: OnceLock::new(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -173,6 +657,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn estimate_parse_and_display() {
+        assert_eq!("3".parse::<Estimate>().unwrap(), Estimate::Points(3.0));
+        assert_eq!("0.5pts".parse::<Estimate>().unwrap(), Estimate::Points(0.5));
+        assert_eq!("4h".parse::<Estimate>().unwrap(), Estimate::Duration(14400));
+        assert!("soon".parse::<Estimate>().is_err());
+        assert!("-1".parse::<Estimate>().is_err());
+        for text in ["5pt", "2d", "1.5pt"] {
+            assert_eq!(text.parse::<Estimate>().unwrap().to_string(), text);
+        }
+        assert_eq!(Estimate::Duration(5400).weight(), 1.5);
+
+        let mut sum = EstimateSum::default();
+        assert!(sum.is_empty());
+        sum.add(Estimate::Points(2.0));
+        sum.add(Estimate::Duration(3600));
+        sum.add(Estimate::Points(1.0));
+        assert_eq!(sum.to_string(), "3pt + 1h");
+        assert_eq!(sum.weight(), 4.0);
+    }
+
+    #[test]
+    fn estimate_overflow_is_an_error() {
+        let err = "99999999999999999w".parse::<Estimate>().unwrap_err();
+        assert!(err.starts_with("invalid estimate"), "{err}");
+    }
+
+    #[test]
+    fn milestone_state_pads() {
+        assert_eq!(format!("{:<8}|", MilestoneState::Open), "open    |");
+        assert_eq!(format!("{:<8}|", MilestoneState::Closed), "closed  |");
+    }
+
     #[test]
     fn display_round_trip_status() {
         for variant in [Status::Open, Status::Closed] {
@@ -199,26 +716,21 @@ mod tests {
 
     #[test]
     fn store_serde_round_trip() {
-        let now = Utc::now();
         let mut store = Store::default();
         store.items.insert(
             "lb-abc1".to_string(),
             Item {
-                id: "lb-abc1".to_string(),
                 title: "Test item".to_string(),
                 description: Some("A description".to_string()),
-                item_type: ItemType::Task,
-                status: Status::Open,
                 priority: 1,
-                claimed_by: None,
-                created_at: now,
-                updated_at: now,
+                ..Item::for_test("lb-abc1")
             },
         );
-        store.deps.push(Dep {
+        store.add_dep(Dep {
             from_id: "lb-abc1".to_string(),
             to_id: "lb-xyz2".to_string(),
             dep_type: DepType::Parent,
+            created_at: None,
         });
 
         let json = serde_json::to_string(&store).unwrap();
@@ -226,23 +738,27 @@ mod tests {
 
         assert_eq!(restored.items.len(), 1);
         assert_eq!(restored.items["lb-abc1"].title, "Test item");
-        assert_eq!(restored.deps.len(), 1);
-        assert_eq!(restored.deps[0].dep_type, DepType::Parent);
+        assert_eq!(restored.deps().len(), 1);
+        assert_eq!(restored.deps()[0].dep_type, DepType::Parent);
+    }
+
+    #[test]
+    fn default_settings_skipped_in_json() {
+        let json = serde_json::to_string(&Store::default()).unwrap();
+        assert!(!json.contains("settings"), "{json}");
+
+        let mut store = Store::default();
+        store.settings.auto_close_parents = true;
+        let json = serde_json::to_string(&store).unwrap();
+        let restored: Store = serde_json::from_str(&json).unwrap();
+        assert!(restored.settings.auto_close_parents);
     }
 
     #[test]
     fn item_description_none_skipped_in_json() {
-        let now = Utc::now();
         let item = Item {
-            id: "lb-test".to_string(),
             title: "No desc".to_string(),
-            description: None,
-            item_type: ItemType::Task,
-            status: Status::Open,
-            priority: 2,
-            claimed_by: None,
-            created_at: now,
-            updated_at: now,
+            ..Item::for_test("lb-test")
         };
         let json = serde_json::to_string(&item).unwrap();
         assert!(!json.contains("description"));
@@ -253,20 +769,53 @@ mod tests {
     fn claimed_by_round_trip() {
         let now = Utc::now();
         let item = Item {
-            id: "lb-test".to_string(),
             title: "Claimed".to_string(),
-            description: None,
-            item_type: ItemType::Task,
-            status: Status::Open,
             priority: 1,
             claimed_by: Some("alice".to_string()),
-            created_at: now,
-            updated_at: now,
+            claimed_at: Some(now),
+            claim_ttl: Some(3600),
+            ..Item::for_test("lb-test")
         };
         let json = serde_json::to_string(&item).unwrap();
         assert!(json.contains("claimed_by"));
         assert!(json.contains("alice"));
         let restored: Item = serde_json::from_str(&json).unwrap();
         assert_eq!(restored.claimed_by.as_deref(), Some("alice"));
+        assert_eq!(restored.claimed_at, Some(now));
+        assert_eq!(restored.claim_ttl, Some(3600));
+    }
+
+    #[test]
+    fn legacy_claim_without_lease_fields() {
+        // Stores written before leases only carry claimed_by
+        let json = r#"{"id":"lb-test","title":"t","item_type":"task","status":"open",
+            "priority":2,"claimed_by":"alice",
+            "created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}"#;
+        let item: Item = serde_json::from_str(json).unwrap();
+        assert_eq!(item.claimed_by.as_deref(), Some("alice"));
+        assert!(item.claimed_at.is_none());
+        assert!(item.claim_ttl.is_none());
+    }
+
+    #[test]
+    fn dep_index_follows_changes() {
+        let dep = |from: &str, to: &str, dep_type| Dep::new(from.into(), to.into(), dep_type);
+        let mut store = Store::default();
+        store.add_dep(dep("lb-a", "lb-b", DepType::Blocks));
+        store.add_dep(dep("lb-c", "lb-a", DepType::Parent));
+        assert_eq!(store.index().blockers("lb-b"), ["lb-a"]);
+        assert_eq!(store.index().parent("lb-c"), Some("lb-a"));
+
+        // Changes after the index is built update it in place
+        store.add_dep(dep("lb-c", "lb-b", DepType::Blocks));
+        store.retain_deps(|d| d.dep_type != DepType::Parent);
+        assert_eq!(store.index().blockers("lb-b"), ["lb-a", "lb-c"]);
+        assert_eq!(store.index().blocking("lb-c"), ["lb-b"]);
+        assert!(store.index().children("lb-a").is_empty());
+        assert_eq!(store.index().parent("lb-c"), None);
+
+        store.set_deps(vec![dep("lb-b", "lb-a", DepType::Parent)]);
+        assert_eq!(store.index().children("lb-a"), ["lb-b"]);
+        assert!(store.index().blockers("lb-b").is_empty());
     }
 }
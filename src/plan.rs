@@ -0,0 +1,243 @@
+//! Execution plans for the open work under an item: a topological order,
+//! the waves of items that can run in parallel, and the critical path.
+
+use crate::model::{Item, Status, Store};
+use crate::store;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Plan {
+    pub root: String,
+    /// Every open descendant, each after everything it waits on.
+    pub order: Vec<String>,
+    /// Groups of items whose prerequisites are all in earlier waves; the
+    /// size of a wave is how many agents could work on it at once.
+    pub waves: Vec<Vec<String>>,
+    /// The heaviest chain of prerequisites, first to last.
+    pub critical_path: Vec<String>,
+    /// Total weight of the critical path.
+    pub critical_path_weight: f64,
+    /// Open blockers from outside the plan, by the item they hold up.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub external_blockers: BTreeMap<String, Vec<String>>,
+}
+
+/// Plan the open descendants of `root`. An item waits on its open blockers
+/// and, since it can't close before them, its open children. `weight` sizes
+/// each item for the critical path.
+pub fn plan(store: &Store, root: &str, weight: impl Fn(&Item) -> f64) -> Result<Plan, String> {
+    let is_open = |id: &str| {
+        store
+            .items
+            .get(id)
+            .is_some_and(|i| i.status == Status::Open)
+    };
+
+    let mut members: BTreeSet<String> = BTreeSet::new();
+    let mut stack = store::get_children(store, root);
+    while let Some(id) = stack.pop() {
+        if is_open(&id) && members.insert(id.clone()) {
+            stack.extend(store::get_children(store, &id));
+        }
+    }
+
+    let mut waits_on: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut external_blockers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for id in &members {
+        let mut prereqs = Vec::new();
+        for blocker in store::get_blockers(store, id) {
+            if members.contains(&blocker) {
+                prereqs.push(blocker);
+            } else if !store
+                .items
+                .get(&blocker)
+                .is_some_and(|b| b.status == Status::Closed)
+            {
+                external_blockers
+                    .entry(id.clone())
+                    .or_default()
+                    .push(blocker);
+            }
+        }
+        prereqs.extend(
+            store::get_children(store, id)
+                .into_iter()
+                .filter(|c| members.contains(c)),
+        );
+        waits_on.insert(id, prereqs);
+    }
+
+    // Kahn's algorithm, taking the most urgent available item first
+    let item = |id: &str| &store.items[id];
+    let key = |id: &str| (item(id).priority, id.to_string());
+    let mut remaining: HashMap<&str, usize> =
+        waits_on.iter().map(|(id, pre)| (*id, pre.len())).collect();
+    let mut unlocks: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (id, prereqs) in &waits_on {
+        for pre in prereqs {
+            unlocks.entry(pre.as_str()).or_default().push(id);
+        }
+    }
+    let mut available: BTreeSet<(u8, String)> = remaining
+        .iter()
+        .filter(|(_, n)| **n == 0)
+        .map(|(id, _)| key(id))
+        .collect();
+    let mut order: Vec<String> = Vec::new();
+    while let Some((_, id)) = available.pop_first() {
+        for next in unlocks.get(id.as_str()).into_iter().flatten() {
+            let n = remaining.get_mut(next).expect("every member is counted");
+            *n -= 1;
+            if *n == 0 {
+                available.insert(key(next));
+            }
+        }
+        order.push(id);
+    }
+    if order.len() < members.len() {
+        let stuck: Vec<&str> = members
+            .iter()
+            .filter(|id| !order.contains(id))
+            .map(String::as_str)
+            .collect();
+        return Err(format!(
+            "blocking cycle among {} — run `lb fsck --repair`",
+            stuck.join(", ")
+        ));
+    }
+
+    let mut wave_of: HashMap<&str, usize> = HashMap::new();
+    let mut heaviest: HashMap<&str, (f64, Option<&str>)> = HashMap::new();
+    let mut waves: Vec<Vec<String>> = Vec::new();
+    for id in &order {
+        let prereqs = &waits_on[id.as_str()];
+        let wave = prereqs
+            .iter()
+            .map(|p| wave_of[p.as_str()] + 1)
+            .max()
+            .unwrap_or(0);
+        wave_of.insert(id, wave);
+        if waves.len() <= wave {
+            waves.resize(wave + 1, Vec::new());
+        }
+        waves[wave].push(id.clone());
+
+        let mut best: (f64, Option<&str>) = (0.0, None);
+        for p in prereqs {
+            let (w, _) = heaviest[p.as_str()];
+            if best.1.is_none() || w > best.0 {
+                best = (w, Some(p.as_str()));
+            }
+        }
+        heaviest.insert(id, (best.0 + weight(item(id)), best.1));
+    }
+    for wave in &mut waves {
+        wave.sort_by_key(|id| key(id));
+    }
+
+    let mut critical_path = Vec::new();
+    let mut critical_path_weight = 0.0;
+    let mut end: Option<&str> = None;
+    for id in &order {
+        let w = heaviest[id.as_str()].0;
+        if end.is_none() || w > critical_path_weight {
+            critical_path_weight = w;
+            end = Some(id);
+        }
+    }
+    while let Some(id) = end {
+        critical_path.push(id.to_string());
+        end = heaviest[id].1;
+    }
+    critical_path.reverse();
+
+    Ok(Plan {
+        root: root.to_string(),
+        order,
+        waves,
+        critical_path,
+        critical_path_weight,
+        external_blockers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Dep, DepType};
+
+    fn add(store: &mut Store, id: &str, priority: u8, parent: Option<&str>) {
+        store.items.insert(
+            id.to_string(),
+            Item {
+                priority,
+                ..Item::for_test(id)
+            },
+        );
+        if let Some(parent) = parent {
+            store.add_dep(Dep::new(id.into(), parent.into(), DepType::Parent));
+        }
+    }
+
+    fn blocks(store: &mut Store, from: &str, to: &str) {
+        store.add_dep(Dep::new(from.into(), to.into(), DepType::Blocks));
+    }
+
+    /// Epic lb-e: lb-a blocks lb-b and lb-c, which both block lb-d; lb-f is
+    /// a feature holding lb-g, blocked by lb-x from outside the epic.
+    fn sample() -> Store {
+        let mut s = Store::default();
+        add(&mut s, "lb-e", 1, None);
+        for (id, pri) in [("lb-a", 2), ("lb-b", 2), ("lb-c", 1), ("lb-d", 2)] {
+            add(&mut s, id, pri, Some("lb-e"));
+        }
+        add(&mut s, "lb-f", 2, Some("lb-e"));
+        add(&mut s, "lb-g", 2, Some("lb-f"));
+        add(&mut s, "lb-x", 2, None);
+        blocks(&mut s, "lb-a", "lb-b");
+        blocks(&mut s, "lb-a", "lb-c");
+        blocks(&mut s, "lb-b", "lb-d");
+        blocks(&mut s, "lb-c", "lb-d");
+        blocks(&mut s, "lb-x", "lb-g");
+        s
+    }
+
+    #[test]
+    fn waves_order_and_critical_path() {
+        let p = plan(&sample(), "lb-e", |_| 1.0).unwrap();
+        assert_eq!(
+            p.waves,
+            vec![
+                vec!["lb-a", "lb-g"],
+                vec!["lb-c", "lb-b", "lb-f"],
+                vec!["lb-d"]
+            ]
+        );
+        assert_eq!(p.order, ["lb-a", "lb-c", "lb-b", "lb-d", "lb-g", "lb-f"]);
+        assert_eq!(p.critical_path, ["lb-a", "lb-b", "lb-d"]);
+        assert_eq!(p.critical_path_weight, 3.0);
+        assert_eq!(p.external_blockers["lb-g"], ["lb-x"]);
+    }
+
+    #[test]
+    fn weights_pick_the_heaviest_chain_and_closed_items_drop_out() {
+        let mut s = sample();
+        let p = plan(&s, "lb-e", |i| if i.id == "lb-c" { 5.0 } else { 1.0 }).unwrap();
+        assert_eq!(p.critical_path, ["lb-a", "lb-c", "lb-d"]);
+        assert_eq!(p.critical_path_weight, 7.0);
+
+        s.items.get_mut("lb-a").unwrap().status = Status::Closed;
+        let p = plan(&s, "lb-e", |_| 1.0).unwrap();
+        assert_eq!(p.waves[0], ["lb-c", "lb-b", "lb-g"]);
+        assert!(!p.order.contains(&"lb-a".to_string()));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let mut s = sample();
+        blocks(&mut s, "lb-d", "lb-a");
+        let err = plan(&s, "lb-e", |_| 1.0).unwrap_err();
+        assert!(err.contains("blocking cycle among lb-a"), "{err}");
+    }
+}
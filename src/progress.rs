@@ -0,0 +1,188 @@
+//! Roll-ups of how far along the work under a parent is.
+
+use crate::model::{EstimateSum, Status, Store};
+use crate::store;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Counts over a set of items.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rollup {
+    pub total: usize,
+    pub closed: usize,
+    /// Open items with an active claim.
+    pub claimed: usize,
+    /// Open items with an unclosed blocker.
+    pub blocked: usize,
+    /// Estimates of every item that has one.
+    pub estimated: EstimateSum,
+    /// Estimates of the closed items among them.
+    pub estimated_closed: EstimateSum,
+}
+
+impl Rollup {
+    /// Share of items closed, 0–100.
+    pub fn percent(&self) -> u32 {
+        if self.total == 0 {
+            return 0;
+        }
+        (self.closed * 100 / self.total) as u32
+    }
+
+    /// Share of estimated work closed, 0–100; `None` without estimates.
+    pub fn estimate_percent(&self) -> Option<u32> {
+        let total = self.estimated.weight();
+        (total > 0.0).then(|| (self.estimated_closed.weight() * 100.0 / total) as u32)
+    }
+}
+
+impl fmt::Display for Rollup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} closed ({}%)",
+            self.closed,
+            self.total,
+            self.percent()
+        )?;
+        if self.claimed > 0 {
+            write!(f, ", {} claimed", self.claimed)?;
+        }
+        if self.blocked > 0 {
+            write!(f, ", {} blocked", self.blocked)?;
+        }
+        if let Some(pct) = self.estimate_percent() {
+            write!(
+                f,
+                "; {}/{} estimated done ({pct}%)",
+                self.estimated_closed, self.estimated
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Every descendant of `id`, not including `id` itself.
+pub fn descendants(store: &Store, id: &str) -> Vec<String> {
+    let mut seen = HashSet::from([id.to_string()]);
+    let mut out = Vec::new();
+    let mut stack = store::get_children(store, id);
+    while let Some(cid) = stack.pop() {
+        if seen.insert(cid.clone()) {
+            stack.extend(store::get_children(store, &cid));
+            out.push(cid);
+        }
+    }
+    out
+}
+
+fn count<'a>(store: &Store, ids: impl IntoIterator<Item = &'a str>, now: DateTime<Utc>) -> Rollup {
+    let mut r = Rollup::default();
+    for item in ids.into_iter().filter_map(|id| store.items.get(id)) {
+        r.total += 1;
+        if let Some(est) = item.estimate {
+            r.estimated.add(est);
+            if item.status == Status::Closed {
+                r.estimated_closed.add(est);
+            }
+        }
+        if item.status == Status::Closed {
+            r.closed += 1;
+            continue;
+        }
+        if store::active_claimant(item, now).is_some() {
+            r.claimed += 1;
+        }
+        if store::is_blocked(store, &item.id) {
+            r.blocked += 1;
+        }
+    }
+    r
+}
+
+/// Roll-up of everything below `id`.
+pub fn rollup(store: &Store, id: &str, now: DateTime<Utc>) -> Rollup {
+    let below = descendants(store, id);
+    count(store, below.iter().map(String::as_str), now)
+}
+
+/// Roll-up of `id` and everything below it, so a leaf counts as one item.
+pub fn subtree(store: &Store, id: &str, now: DateTime<Utc>) -> Rollup {
+    let below = descendants(store, id);
+    count(
+        store,
+        std::iter::once(id).chain(below.iter().map(String::as_str)),
+        now,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Dep, DepType, Estimate, Item};
+
+    fn add(store: &mut Store, id: &str, parent: Option<&str>) {
+        store.items.insert(id.to_string(), Item::for_test(id));
+        if let Some(parent) = parent {
+            store.add_dep(Dep::new(id.into(), parent.into(), DepType::Parent));
+        }
+    }
+
+    /// Epic lb-e holds lb-a (closed), lb-b (claimed) and feature lb-f, which
+    /// holds lb-c (blocked by lb-b) and lb-d.
+    fn sample() -> Store {
+        let mut s = Store::default();
+        add(&mut s, "lb-e", None);
+        add(&mut s, "lb-a", Some("lb-e"));
+        add(&mut s, "lb-b", Some("lb-e"));
+        add(&mut s, "lb-f", Some("lb-e"));
+        add(&mut s, "lb-c", Some("lb-f"));
+        add(&mut s, "lb-d", Some("lb-f"));
+        s.items.get_mut("lb-a").unwrap().status = Status::Closed;
+        s.items.get_mut("lb-b").unwrap().claimed_by = Some("alice".to_string());
+        s.add_dep(Dep::new("lb-b".into(), "lb-c".into(), DepType::Blocks));
+        s
+    }
+
+    #[test]
+    fn rolls_up_every_level() {
+        let s = sample();
+        let now = Utc::now();
+        let r = rollup(&s, "lb-e", now);
+        assert_eq!(
+            r,
+            Rollup {
+                total: 5,
+                closed: 1,
+                claimed: 1,
+                blocked: 1,
+                ..Rollup::default()
+            }
+        );
+        assert_eq!(r.to_string(), "1/5 closed (20%), 1 claimed, 1 blocked");
+        assert_eq!(rollup(&s, "lb-f", now).total, 2);
+        assert_eq!(subtree(&s, "lb-f", now).total, 3);
+        assert_eq!(subtree(&s, "lb-a", now).to_string(), "1/1 closed (100%)");
+        assert_eq!(rollup(&s, "lb-a", now), Rollup::default());
+    }
+
+    #[test]
+    fn estimates_weigh_progress() {
+        let mut s = sample();
+        let now = Utc::now();
+        s.items.get_mut("lb-a").unwrap().estimate = Some(Estimate::Points(3.0));
+        s.items.get_mut("lb-c").unwrap().estimate = Some(Estimate::Points(1.0));
+        s.items.get_mut("lb-d").unwrap().estimate = Some(Estimate::Duration(7200));
+        let r = rollup(&s, "lb-e", now);
+        assert_eq!(r.estimated.to_string(), "4pt + 2h");
+        assert_eq!(r.estimate_percent(), Some(50));
+        assert!(
+            r.to_string()
+                .ends_with("; 3pt/4pt + 2h estimated done (50%)"),
+            "{r}"
+        );
+        assert_eq!(rollup(&s, "lb-f", now).estimate_percent(), Some(0));
+        assert_eq!(rollup(&s, "lb-b", now).estimate_percent(), None);
+    }
+}
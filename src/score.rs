@@ -0,0 +1,285 @@
+//! Ranking `lb ready`: each item's score is a weighted sum of factors, with
+//! the weights taken from the repository settings.
+
+use crate::model::{Item, ItemType, ScoreWeights, Store};
+use crate::{due, store, why};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Factor {
+    Priority,
+    Age,
+    Unblocks,
+    Parent,
+    Due,
+}
+
+impl Factor {
+    pub const ALL: [Factor; 5] = [
+        Factor::Priority,
+        Factor::Age,
+        Factor::Unblocks,
+        Factor::Parent,
+        Factor::Due,
+    ];
+
+    fn weight(self, weights: &ScoreWeights) -> f64 {
+        match self {
+            Factor::Priority => weights.priority,
+            Factor::Age => weights.age,
+            Factor::Unblocks => weights.unblocks,
+            Factor::Parent => weights.parent,
+            Factor::Due => weights.due,
+        }
+    }
+
+    /// The unweighted measure, bigger meaning more worth doing now, and a
+    /// short human description of it.
+    fn measure(self, store: &Store, item: &Item, now: DateTime<Utc>) -> (f64, String) {
+        match self {
+            Factor::Priority => (urgency(item.priority), format!("P{}", item.priority)),
+            Factor::Age => {
+                let days = (now - item.created_at).num_seconds().max(0) as f64 / 86_400.0;
+                (days, format!("{days:.1}d old"))
+            }
+            Factor::Unblocks => {
+                let n = why::unblocks(store, &item.id).len();
+                (n as f64, format!("unblocks {n}"))
+            }
+            Factor::Parent => match enclosing_epic(store, &item.id) {
+                Some(epic) => (
+                    urgency(epic.priority),
+                    format!("epic {} P{}", epic.id, epic.priority),
+                ),
+                None => (0.0, "no epic".to_string()),
+            },
+            // Inherited deadlines count too: an epic due Friday makes its
+            // tasks urgent
+            Factor::Due => match due::deadline(store, &item.id) {
+                Some(d) => {
+                    let today = now.with_timezone(&chrono::Local).date_naive();
+                    let left = (d.date - today).num_days();
+                    (
+                        (DUE_HORIZON_DAYS - left).max(0) as f64,
+                        format!("due {}", due::describe(d.date, today)),
+                    )
+                }
+                None => (0.0, "no due date".to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Factor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Factor::Priority => write!(f, "priority"),
+            Factor::Age => write!(f, "age"),
+            Factor::Unblocks => write!(f, "unblocks"),
+            Factor::Parent => write!(f, "parent"),
+            Factor::Due => write!(f, "due"),
+        }
+    }
+}
+
+/// Deadlines further out than this don't add to the score yet.
+const DUE_HORIZON_DAYS: i64 = 14;
+
+/// Levels above P4, so P0 counts most.
+fn urgency(priority: u8) -> f64 {
+    4.0 - priority as f64
+}
+
+/// The nearest epic among `id`'s ancestors.
+fn enclosing_epic<'a>(store: &'a Store, id: &str) -> Option<&'a Item> {
+    let mut seen = HashSet::new();
+    let mut cur = store::get_parent(store, id)?;
+    while seen.insert(cur.clone()) {
+        if let Some(item) = store.items.get(&cur)
+            && item.item_type == ItemType::Epic
+        {
+            return Some(item);
+        }
+        cur = store::get_parent(store, &cur)?;
+    }
+    None
+}
+
+/// One factor's share of a score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub factor: Factor,
+    pub detail: String,
+    pub points: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    pub total: f64,
+    pub parts: Vec<Part>,
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} =", self.total)?;
+        for (i, part) in self.parts.iter().enumerate() {
+            let sep = if i == 0 { "" } else { " +" };
+            write!(
+                f,
+                "{sep} {} {:.1} ({})",
+                part.factor, part.points, part.detail
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Score `item` with the built-in factors; factors weighted zero are left out.
+pub fn score(store: &Store, item: &Item, weights: &ScoreWeights, now: DateTime<Utc>) -> Score {
+    let parts: Vec<Part> = Factor::ALL
+        .into_iter()
+        .filter(|f| f.weight(weights) != 0.0)
+        .map(|factor| {
+            let (value, detail) = factor.measure(store, item, now);
+            Part {
+                factor,
+                detail,
+                points: value * factor.weight(weights),
+            }
+        })
+        .collect();
+    Score {
+        total: parts.iter().map(|p| p.points).sum(),
+        parts,
+    }
+}
+
+/// Pair each item with its score from `scorer`, best first. Ties keep their
+/// order in `items`.
+pub fn rank(items: Vec<&Item>, scorer: impl Fn(&Item) -> Score) -> Vec<(&Item, Score)> {
+    let mut ranked: Vec<(&Item, Score)> = items.into_iter().map(|i| (i, scorer(i))).collect();
+    ranked.sort_by(|a, b| b.1.total.total_cmp(&a.1.total));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Dep, DepType};
+    use chrono::Duration;
+
+    fn add(store: &mut Store, id: &str, item_type: ItemType, priority: u8, age_days: i64) {
+        let created = Utc::now() - Duration::days(age_days);
+        store.items.insert(
+            id.to_string(),
+            Item {
+                item_type,
+                priority,
+                created_at: created,
+                updated_at: created,
+                ..Item::for_test(id)
+            },
+        );
+    }
+
+    /// Epic lb-e (P0) holds feature lb-f, which holds task lb-a (P2, which
+    /// blocks lb-b); lb-c is a P1 task on its own, created 30 days ago.
+    fn sample() -> Store {
+        let mut s = Store::default();
+        add(&mut s, "lb-e", ItemType::Epic, 0, 0);
+        add(&mut s, "lb-f", ItemType::Feature, 3, 0);
+        add(&mut s, "lb-a", ItemType::Task, 2, 0);
+        add(&mut s, "lb-b", ItemType::Task, 2, 0);
+        add(&mut s, "lb-c", ItemType::Task, 1, 30);
+        for (from, to, dep_type) in [
+            ("lb-f", "lb-e", DepType::Parent),
+            ("lb-a", "lb-f", DepType::Parent),
+            ("lb-a", "lb-b", DepType::Blocks),
+        ] {
+            s.add_dep(Dep::new(from.to_string(), to.to_string(), dep_type));
+        }
+        s
+    }
+
+    #[test]
+    fn factors_add_up() {
+        let s = sample();
+        let now = Utc::now();
+        let sc = score(&s, &s.items["lb-a"], &ScoreWeights::default(), now);
+        let points: Vec<(Factor, f64)> = sc.parts.iter().map(|p| (p.factor, p.points)).collect();
+        assert_eq!(
+            points,
+            vec![
+                (Factor::Priority, 20.0),
+                (Factor::Age, 0.0),
+                (Factor::Unblocks, 1.0),
+                (Factor::Parent, 8.0),
+                (Factor::Due, 0.0),
+            ]
+        );
+        assert_eq!(sc.total, 29.0);
+        assert!(
+            sc.to_string()
+                .starts_with("29.0 = priority 20.0 (P2) + age 0.0 (0.0d old)"),
+            "{sc}"
+        );
+        assert!(
+            sc.to_string()
+                .ends_with("parent 8.0 (epic lb-e P0) + due 0.0 (no due date)"),
+            "{sc}"
+        );
+
+        let c = score(&s, &s.items["lb-c"], &ScoreWeights::default(), now);
+        assert!((c.total - 33.0).abs() < 0.01, "{c}");
+    }
+
+    #[test]
+    fn weights_change_the_ranking() {
+        let s = sample();
+        let now = Utc::now();
+        let ready = vec![&s.items["lb-a"], &s.items["lb-c"]];
+        let ids = |ranked: Vec<(&Item, Score)>| -> Vec<String> {
+            ranked.into_iter().map(|(i, _)| i.id.clone()).collect()
+        };
+
+        let defaults = ScoreWeights::default();
+        assert_eq!(
+            ids(rank(ready.clone(), |i| score(&s, i, &defaults, now))),
+            ["lb-c", "lb-a"]
+        );
+
+        let epic_first = ScoreWeights {
+            parent: 5.0,
+            age: 0.0,
+            ..defaults
+        };
+        let ranked = rank(ready, |i| score(&s, i, &epic_first, now));
+        assert!(ranked[0].1.parts.iter().all(|p| p.factor != Factor::Age));
+        assert_eq!(ids(ranked), ["lb-a", "lb-c"]);
+    }
+
+    #[test]
+    fn deadlines_add_urgency_and_are_inherited() {
+        let mut s = sample();
+        let now = Utc::now();
+        let today = now.with_timezone(&chrono::Local).date_naive();
+        let only_due = ScoreWeights {
+            priority: 0.0,
+            age: 0.0,
+            unblocks: 0.0,
+            parent: 0.0,
+            due: 1.0,
+        };
+        s.items.get_mut("lb-e").unwrap().due = Some(today + Duration::days(4));
+        s.items.get_mut("lb-c").unwrap().due = Some(today - Duration::days(2));
+        s.items.get_mut("lb-b").unwrap().due = Some(today + Duration::days(30));
+
+        let a = score(&s, &s.items["lb-a"], &only_due, now);
+        assert_eq!(a.to_string(), "10.0 = due 10.0 (due in 4d)");
+        let c = score(&s, &s.items["lb-c"], &only_due, now);
+        assert_eq!(c.to_string(), "16.0 = due 16.0 (due 2d overdue)");
+        assert_eq!(score(&s, &s.items["lb-b"], &only_due, now).total, 0.0);
+    }
+}
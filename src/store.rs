@@ -1,13 +1,24 @@
+use crate::fsck::{self, Problem};
 use crate::id::generate_id;
+use crate::migrate;
+use crate::milestone;
 use crate::model::*;
-use chrono::Utc;
-use std::collections::HashSet;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashSet};
 
 pub fn from_json(json: &str) -> Result<Store, String> {
-    serde_json::from_str(json).map_err(|e| format!("invalid store JSON: {e}"))
+    let value = serde_json::from_str(json).map_err(|e| format!("invalid store JSON: {e}"))?;
+    from_value(value)
+}
+
+/// Deserialize a raw store, first upgrading it from older schemas.
+pub fn from_value(mut value: serde_json::Value) -> Result<Store, String> {
+    migrate::upgrade(&mut value)?;
+    serde_json::from_value(value).map_err(|e| format!("invalid store JSON: {e}"))
 }
 
 pub fn to_json(store: &Store) -> Result<String, String> {
+    migrate::check_writable(store.schema_version)?;
     serde_json::to_string_pretty(store).map_err(|e| format!("failed to serialize store: {e}"))
 }
 
@@ -36,11 +47,21 @@ pub fn resolve_id(store: &Store, prefix: &str) -> Result<String, String> {
     }
 }
 
+/// Planning fields a new item can start with.
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    pub estimate: Option<Estimate>,
+    pub due: Option<NaiveDate>,
+    /// Must name an open milestone.
+    pub milestone: Option<String>,
+}
+
 pub fn create_item(
     store: &mut Store,
     title: String,
     item_type: ItemType,
     priority: u8,
+    schedule: Schedule,
     description: Option<String>,
     parent_id: Option<String>,
 ) -> Result<String, String> {
@@ -50,6 +71,9 @@ pub fn create_item(
             return Err(format!("parent '{resolved}' not found"));
         }
     }
+    if let Some(ref name) = schedule.milestone {
+        milestone::check_open(store, name)?;
+    }
 
     let existing: Vec<&str> = store.items.keys().map(|s| s.as_str()).collect();
     let id = generate_id(&title, &existing);
@@ -61,19 +85,22 @@ pub fn create_item(
         item_type,
         status: Status::Open,
         priority,
+        estimate: schedule.estimate,
+        due: schedule.due,
+        milestone: schedule.milestone,
         claimed_by: None,
+        claimed_at: None,
+        claim_ttl: None,
         created_at: now,
         updated_at: now,
+        history: Vec::new(),
+        extra: Extra::new(),
     };
     store.items.insert(id.clone(), item);
 
     if let Some(pid) = parent_id {
         let resolved = resolve_id(store, &pid)?;
-        store.deps.push(Dep {
-            from_id: id.clone(),
-            to_id: resolved,
-            dep_type: DepType::Parent,
-        });
+        store.add_dep(Dep::new(id.clone(), resolved, DepType::Parent));
     }
 
     Ok(id)
@@ -101,73 +128,181 @@ fn delete_item_recursive(
         .items
         .remove(id)
         .ok_or_else(|| format!("item '{id}' not found"))?;
-    store.deps.retain(|d| d.from_id != id && d.to_id != id);
+    store.retain_deps(|d| d.from_id != id && d.to_id != id);
     deleted.push(id.to_string());
     Ok(())
 }
 
 /// Close an item, rejecting if it has open children.
 /// Clears `claimed_by` on success.
-pub fn close_item(store: &mut Store, id: &str) -> Result<(), String> {
+///
+/// Returns the closed item IDs: the item itself, followed by any ancestors
+/// closed because `auto_close_parents` is set and their last open child closed.
+pub fn close_item(store: &mut Store, id: &str) -> Result<Vec<String>, String> {
     let id = resolve_id(store, id)?;
-    let children = get_children(store, &id);
-    let open_children: Vec<_> = children
-        .iter()
-        .filter_map(|cid| {
-            store
-                .items
-                .get(cid)
-                .filter(|item| item.status == Status::Open)
-                .map(|item| format!("  {} {}", cid, item.title))
-        })
-        .collect();
+    let open_children = open_children(store, &id);
     if !open_children.is_empty() {
+        let lines: Vec<String> = open_children
+            .iter()
+            .map(|cid| format!("  {} {}", cid, store.items[cid].title))
+            .collect();
         return Err(format!(
             "cannot close '{}': has open children:\n{}",
             id,
-            open_children.join("\n")
+            lines.join("\n")
         ));
     }
-    let item = store.items.get_mut(&id).ok_or("item not found")?;
+    mark_closed(store, &id)?;
+    let mut closed = vec![id.clone()];
+    closed.extend(auto_close_ancestors(store, &id));
+    Ok(closed)
+}
+
+/// Close an item together with all of its open descendants.
+///
+/// Descendants are closed deepest-first so the returned order reads like a
+/// sequence of ordinary closes; the item itself and any auto-closed ancestors
+/// come last.
+pub fn close_cascade(store: &mut Store, id: &str) -> Result<Vec<String>, String> {
+    let id = resolve_id(store, id)?;
+    if !store.items.contains_key(&id) {
+        return Err(format!("item '{id}' not found"));
+    }
+    let mut closed = Vec::new();
+    close_descendants(store, &id, &mut closed)?;
+    mark_closed(store, &id)?;
+    closed.push(id.clone());
+    closed.extend(auto_close_ancestors(store, &id));
+    Ok(closed)
+}
+
+fn close_descendants(store: &mut Store, id: &str, closed: &mut Vec<String>) -> Result<(), String> {
+    for child in get_children(store, id) {
+        close_descendants(store, &child, closed)?;
+        if store
+            .items
+            .get(&child)
+            .is_some_and(|c| c.status == Status::Open)
+        {
+            mark_closed(store, &child)?;
+            closed.push(child);
+        }
+    }
+    Ok(())
+}
+
+fn open_children(store: &Store, id: &str) -> Vec<String> {
+    get_children(store, id)
+        .into_iter()
+        .filter(|cid| {
+            store
+                .items
+                .get(cid)
+                .is_some_and(|item| item.status == Status::Open)
+        })
+        .collect()
+}
+
+fn mark_closed(store: &mut Store, id: &str) -> Result<(), String> {
+    let item = store
+        .items
+        .get_mut(id)
+        .ok_or_else(|| format!("item '{id}' not found"))?;
     item.status = Status::Closed;
-    item.claimed_by = None;
+    clear_claim(item);
     item.updated_at = Utc::now();
     Ok(())
 }
 
+/// Walk up from a just-closed item, closing each open parent whose children
+/// are now all closed. No-op unless `auto_close_parents` is enabled.
+fn auto_close_ancestors(store: &mut Store, id: &str) -> Vec<String> {
+    let mut closed = Vec::new();
+    if !store.settings.auto_close_parents {
+        return closed;
+    }
+    let mut cur = id.to_string();
+    while let Some(pid) = get_parent(store, &cur) {
+        let parent_open = store
+            .items
+            .get(&pid)
+            .is_some_and(|p| p.status == Status::Open);
+        if !parent_open || !open_children(store, &pid).is_empty() {
+            break;
+        }
+        if mark_closed(store, &pid).is_err() {
+            break;
+        }
+        closed.push(pid.clone());
+        cur = pid;
+    }
+    closed
+}
+
+/// Current settings as `(key, value)` pairs, for `lb config`.
+pub fn config_entries(store: &Store) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "auto_close_parents",
+            store.settings.auto_close_parents.to_string(),
+        ),
+        ("layout", store.settings.layout.to_string()),
+        ("score.priority", store.settings.score.priority.to_string()),
+        ("score.age", store.settings.score.age.to_string()),
+        ("score.unblocks", store.settings.score.unblocks.to_string()),
+        ("score.parent", store.settings.score.parent.to_string()),
+        ("score.due", store.settings.score.due.to_string()),
+    ]
+}
+
+pub fn set_config(store: &mut Store, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "auto_close_parents" => {
+            store.settings.auto_close_parents = parse_bool(value)?;
+        }
+        "layout" => {
+            store.settings.layout = value.parse()?;
+        }
+        "score.priority" => store.settings.score.priority = parse_weight(value)?,
+        "score.age" => store.settings.score.age = parse_weight(value)?,
+        "score.unblocks" => store.settings.score.unblocks = parse_weight(value)?,
+        "score.parent" => store.settings.score.parent = parse_weight(value)?,
+        "score.due" => store.settings.score.due = parse_weight(value)?,
+        _ => return Err(format!("unknown config key: {key}")),
+    }
+    Ok(())
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("invalid boolean: {value} (valid: true, false)")),
+    }
+}
+
+fn parse_weight(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|w| w.is_finite())
+        .ok_or_else(|| format!("invalid weight: {value} (expected a number)"))
+}
+
 pub fn get_children(store: &Store, id: &str) -> Vec<String> {
-    store
-        .deps
-        .iter()
-        .filter(|d| d.dep_type == DepType::Parent && d.to_id == id)
-        .map(|d| d.from_id.clone())
-        .collect()
+    store.index().children(id).to_vec()
 }
 
 pub fn get_parent(store: &Store, id: &str) -> Option<String> {
-    store
-        .deps
-        .iter()
-        .find(|d| d.dep_type == DepType::Parent && d.from_id == id)
-        .map(|d| d.to_id.clone())
+    store.index().parent(id).map(str::to_string)
 }
 
 pub fn get_blockers(store: &Store, id: &str) -> Vec<String> {
-    store
-        .deps
-        .iter()
-        .filter(|d| d.dep_type == DepType::Blocks && d.to_id == id)
-        .map(|d| d.from_id.clone())
-        .collect()
+    store.index().blockers(id).to_vec()
 }
 
 pub fn get_blocking(store: &Store, id: &str) -> Vec<String> {
-    store
-        .deps
-        .iter()
-        .filter(|d| d.dep_type == DepType::Blocks && d.from_id == id)
-        .map(|d| d.to_id.clone())
-        .collect()
+    store.index().blocking(id).to_vec()
 }
 
 pub fn add_blocking_dep(store: &mut Store, blocker: &str, blocked: &str) -> Result<(), String> {
@@ -176,12 +311,8 @@ pub fn add_blocking_dep(store: &mut Store, blocker: &str, blocked: &str) -> Resu
     if blocker == blocked {
         return Err("item cannot block itself".to_string());
     }
-    let dep = Dep {
-        from_id: blocker.clone(),
-        to_id: blocked.clone(),
-        dep_type: DepType::Blocks,
-    };
-    if store.deps.contains(&dep) {
+    let dep = Dep::new(blocker.clone(), blocked.clone(), DepType::Blocks);
+    if store.index().blocking(&blocker).contains(&blocked) {
         return Err("dependency already exists".to_string());
     }
     // Walk the blocking chain from blocker: if blocked is reachable, adding
@@ -199,16 +330,16 @@ pub fn add_blocking_dep(store: &mut Store, blocker: &str, blocked: &str) -> Resu
             stack.push(b);
         }
     }
-    store.deps.push(dep);
+    store.add_dep(dep);
     Ok(())
 }
 
 pub fn remove_dep(store: &mut Store, from: &str, to: &str) -> Result<(), String> {
     let from = resolve_id(store, from)?;
     let to = resolve_id(store, to)?;
-    let before = store.deps.len();
-    store.deps.retain(|d| !(d.from_id == from && d.to_id == to));
-    if store.deps.len() == before {
+    let before = store.deps().len();
+    store.retain_deps(|d| !(d.from_id == from && d.to_id == to));
+    if store.deps().len() == before {
         return Err(format!("no dependency from '{from}' to '{to}'"));
     }
     Ok(())
@@ -233,38 +364,360 @@ pub fn set_parent(store: &mut Store, child: &str, parent: &str) -> Result<(), St
         }
     }
     // Remove existing parent dep
-    store
-        .deps
-        .retain(|d| !(d.from_id == child && d.dep_type == DepType::Parent));
-    store.deps.push(Dep {
-        from_id: child,
-        to_id: parent,
-        dep_type: DepType::Parent,
+    store.retain_deps(|d| !(d.from_id == child && d.dep_type == DepType::Parent));
+    store.add_dep(Dep::new(child, parent, DepType::Parent));
+    Ok(())
+}
+
+/// When the item's claim lease runs out, if it has a TTL.
+pub fn claim_expires_at(item: &Item) -> Option<DateTime<Utc>> {
+    let ttl = Duration::try_seconds(i64::try_from(item.claim_ttl?).ok()?)?;
+    item.claimed_at?.checked_add_signed(ttl)
+}
+
+/// A claim is stale once its lease has expired. Claims without a TTL
+/// (including legacy claims with no `claimed_at`) never go stale.
+pub fn is_claim_stale(item: &Item, now: DateTime<Utc>) -> bool {
+    item.claimed_by.is_some() && claim_expires_at(item).is_some_and(|exp| exp <= now)
+}
+
+/// Who currently holds the item, ignoring expired claims.
+pub fn active_claimant(item: &Item, now: DateTime<Utc>) -> Option<&str> {
+    if is_claim_stale(item, now) {
+        None
+    } else {
+        item.claimed_by.as_deref()
+    }
+}
+
+/// Claim an item for `user`. An expired claim held by someone else is
+/// taken over; an active one is an error.
+pub fn claim_item(
+    store: &mut Store,
+    id: &str,
+    user: &str,
+    ttl: Option<u64>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let id = resolve_id(store, id)?;
+    let item = store.items.get_mut(&id).ok_or("item not found")?;
+    if item.status == Status::Closed {
+        return Err(format!("item {id} is closed"));
+    }
+    if let Some(who) = active_claimant(item, now) {
+        return Err(format!("item {id} already claimed by {who}"));
+    }
+    if let Some(prev) = item.claimed_by.clone()
+        && prev != user
+    {
+        item.history.push(Event {
+            at: now,
+            actor: user.to_string(),
+            kind: EventKind::Steal,
+            from: Some(prev),
+            to: Some(user.to_string()),
+            reason: Some("lease expired".to_string()),
+        });
+    }
+    item.claimed_by = Some(user.to_string());
+    item.claimed_at = Some(now);
+    item.claim_ttl = ttl;
+    item.updated_at = now;
+    Ok(())
+}
+
+/// Whether `user` could claim `id` right now without stealing: `Ok(true)`
+/// if they already hold it, `Ok(false)` if it is free, `Err` with the reason
+/// otherwise. `id` must be a full item ID.
+pub fn claim_status(
+    store: &Store,
+    id: &str,
+    user: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, String> {
+    let item = store
+        .items
+        .get(id)
+        .ok_or_else(|| format!("item {id} no longer exists"))?;
+    if item.status == Status::Closed {
+        return Err(format!("item {id} is closed"));
+    }
+    match active_claimant(item, now) {
+        Some(who) if who == user => Ok(true),
+        Some(who) => Err(format!("item {id} already claimed by {who}")),
+        None => Ok(false),
+    }
+}
+
+/// Restart the lease on a claim `user` already holds. A new TTL replaces the
+/// old one; otherwise the existing TTL is kept.
+pub fn renew_claim(
+    store: &mut Store,
+    id: &str,
+    user: &str,
+    ttl: Option<u64>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let id = resolve_id(store, id)?;
+    let item = store.items.get_mut(&id).ok_or("item not found")?;
+    match item.claimed_by.as_deref() {
+        None => return Err(format!("item {id} is not claimed")),
+        Some(who) if who != user => {
+            return Err(format!("item {id} is claimed by {who}, not {user}"));
+        }
+        Some(_) => {}
+    }
+    item.claimed_at = Some(now);
+    if ttl.is_some() {
+        item.claim_ttl = ttl;
+    }
+    item.updated_at = now;
+    Ok(())
+}
+
+/// Take over a claim someone else holds, recording the previous claimant
+/// and reason in the item's history. Returns the previous claimant.
+pub fn steal_claim(
+    store: &mut Store,
+    id: &str,
+    user: &str,
+    reason: Option<String>,
+    ttl: Option<u64>,
+    now: DateTime<Utc>,
+) -> Result<String, String> {
+    let id = resolve_id(store, id)?;
+    let item = store.items.get_mut(&id).ok_or("item not found")?;
+    if item.status == Status::Closed {
+        return Err(format!("item {id} is closed"));
+    }
+    let prev = match item.claimed_by.clone() {
+        None => return Err(format!("item {id} is not claimed — use `lb claim`")),
+        Some(who) if who == user => return Err(format!("item {id} is already yours")),
+        Some(who) => who,
+    };
+    item.history.push(Event {
+        at: now,
+        actor: user.to_string(),
+        kind: EventKind::Steal,
+        from: Some(prev.clone()),
+        to: Some(user.to_string()),
+        reason,
+    });
+    item.claimed_by = Some(user.to_string());
+    item.claimed_at = Some(now);
+    item.claim_ttl = ttl;
+    item.updated_at = now;
+    Ok(prev)
+}
+
+/// Reassign an item's claim to `to`. Handing off a claim held by someone
+/// other than `user` requires `force`. The lease TTL carries over.
+/// Returns the previous claimant, if any.
+pub fn handoff_claim(
+    store: &mut Store,
+    id: &str,
+    user: &str,
+    to: &str,
+    reason: Option<String>,
+    force: bool,
+    now: DateTime<Utc>,
+) -> Result<Option<String>, String> {
+    let id = resolve_id(store, id)?;
+    let item = store.items.get_mut(&id).ok_or("item not found")?;
+    if item.status == Status::Closed {
+        return Err(format!("item {id} is closed"));
+    }
+    let prev = item.claimed_by.clone();
+    if let Some(ref who) = prev {
+        if who == to {
+            return Err(format!("item {id} already claimed by {to}"));
+        }
+        if who != user && !force {
+            return Err(format!(
+                "item {id} is claimed by {who} — use --force to hand off someone else's claim"
+            ));
+        }
+    }
+    item.history.push(Event {
+        at: now,
+        actor: user.to_string(),
+        kind: EventKind::Handoff,
+        from: prev.clone(),
+        to: Some(to.to_string()),
+        reason,
     });
+    item.claimed_by = Some(to.to_string());
+    item.claimed_at = Some(now);
+    item.updated_at = now;
+    Ok(prev)
+}
+
+/// Release a claim. Releasing someone else's claim requires `force` and is
+/// recorded in the item's history.
+pub fn release_claim(
+    store: &mut Store,
+    id: &str,
+    user: &str,
+    force: bool,
+    reason: Option<String>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let id = resolve_id(store, id)?;
+    let item = store.items.get_mut(&id).ok_or("item not found")?;
+    let Some(who) = item.claimed_by.clone() else {
+        return Err(format!("item {id} is not claimed"));
+    };
+    if who != user {
+        if !force {
+            return Err(format!(
+                "item {id} is claimed by {who} — use --force to release someone else's claim"
+            ));
+        }
+        item.history.push(Event {
+            at: now,
+            actor: user.to_string(),
+            kind: EventKind::ForceUnclaim,
+            from: Some(who),
+            to: None,
+            reason,
+        });
+    }
+    clear_claim(item);
+    item.updated_at = now;
     Ok(())
 }
 
-/// Items that are open, unclaimed, with no unresolved (non-closed) blockers, sorted by priority.
+/// Release stale claims so the items show up in `lb ready` again.
+/// Returns `(id, previous claimant)` for each released claim.
+pub fn release_stale_claims(store: &mut Store, now: DateTime<Utc>) -> Vec<(String, String)> {
+    let mut released = Vec::new();
+    for item in store.items.values_mut() {
+        if is_claim_stale(item, now)
+            && let Some(who) = item.claimed_by.clone()
+        {
+            clear_claim(item);
+            item.updated_at = now;
+            released.push((item.id.clone(), who));
+        }
+    }
+    released
+}
+
+pub fn clear_claim(item: &mut Item) {
+    item.claimed_by = None;
+    item.claimed_at = None;
+    item.claim_ttl = None;
+}
+
+/// Parse a duration like "30m", "4h", "2d" or "1w" into seconds.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num
+        .parse()
+        .map_err(|_| format!("invalid duration: {s} (e.g. 30m, 4h, 2d)"))?;
+    let mult = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        _ => {
+            return Err(format!(
+                "invalid duration unit in {s} (valid: s, m, h, d, w)"
+            ));
+        }
+    };
+    if n == 0 {
+        return Err("duration must be positive".to_string());
+    }
+    n.checked_mul(mult)
+        .ok_or_else(|| format!("duration too large: {s}"))
+}
+
+/// Parse a claim lease length, rejecting one too long to add to a date so
+/// it never reaches the store.
+pub fn parse_ttl(s: &str) -> Result<u64, String> {
+    let secs = parse_duration(s)?;
+    i64::try_from(secs)
+        .ok()
+        .and_then(Duration::try_seconds)
+        .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+        .map(|_| secs)
+        .ok_or_else(|| format!("lease too long: {s}"))
+}
+
+/// Compact rendering of a duration in seconds, e.g. "2d4h" or "35m".
+pub fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (d, h, m) = (secs / 86400, (secs % 86400) / 3600, (secs % 3600) / 60);
+    match (d, h, m) {
+        (0, 0, 0) => format!("{secs}s"),
+        (0, 0, m) => format!("{m}m"),
+        (0, h, 0) => format!("{h}h"),
+        (0, h, m) => format!("{h}h{m}m"),
+        (d, 0, _) => format!("{d}d"),
+        (d, h, _) => format!("{d}d{h}h"),
+    }
+}
+
+/// Items that are open, unclaimed (or whose claim lease has expired), with
+/// no unresolved (non-closed) blockers, sorted by priority.
 pub fn ready_items(store: &Store) -> Vec<&Item> {
+    let now = Utc::now();
     let mut items: Vec<&Item> = store
         .items
         .values()
         .filter(|item| item.status == Status::Open)
-        .filter(|item| item.claimed_by.is_none())
-        .filter(|item| {
-            let blockers = get_blockers(store, &item.id);
-            blockers.iter().all(|bid| {
-                store
-                    .items
-                    .get(bid)
-                    .is_some_and(|b| b.status == Status::Closed)
-            })
-        })
+        .filter(|item| active_claimant(item, now).is_none())
+        .filter(|item| !is_blocked(store, &item.id))
         .collect();
     items.sort_by_key(|i| i.priority);
     items
 }
 
+/// Open, unblocked items whose active claim is held by `actor` — the
+/// claimant's own queue of workable items, sorted by priority.
+pub fn ready_claimed_by<'a>(store: &'a Store, actor: &str) -> Vec<&'a Item> {
+    let now = Utc::now();
+    let mut items: Vec<&Item> = store
+        .items
+        .values()
+        .filter(|item| item.status == Status::Open)
+        .filter(|item| active_claimant(item, now) == Some(actor))
+        .filter(|item| !is_blocked(store, &item.id))
+        .collect();
+    items.sort_by_key(|i| i.priority);
+    items
+}
+
+/// True if any blocker is not closed (a dangling blocker ID counts as open).
+pub fn is_blocked(store: &Store, id: &str) -> bool {
+    get_blockers(store, id).iter().any(|bid| {
+        store
+            .items
+            .get(bid)
+            .is_none_or(|b| b.status != Status::Closed)
+    })
+}
+
+/// True if `ancestor` is somewhere up `id`'s parent chain.
+pub fn is_descendant(store: &Store, id: &str, ancestor: &str) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    let mut cur = id.to_string();
+    while let Some(pid) = get_parent(store, &cur) {
+        if pid == ancestor {
+            return true;
+        }
+        if !seen.insert(pid.clone()) {
+            return false;
+        }
+        cur = pid;
+    }
+    false
+}
+
 /// Get root items (no parent) for tree display.
 pub fn root_items(store: &Store) -> Vec<&Item> {
     store
@@ -274,15 +727,60 @@ pub fn root_items(store: &Store) -> Vec<&Item> {
         .collect()
 }
 
+/// How an item differs between two versions of a store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemChange {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// Items that differ between `before` and `after`, in ID order.
+pub fn changed_items(before: &Store, after: &Store) -> Vec<(String, ItemChange)> {
+    let ids: std::collections::BTreeSet<&String> =
+        before.items.keys().chain(after.items.keys()).collect();
+    ids.into_iter()
+        .filter_map(|id| {
+            let change = match (before.items.get(id), after.items.get(id)) {
+                (None, Some(_)) => ItemChange::Added,
+                (Some(_), None) => ItemChange::Removed,
+                (Some(old), Some(new)) if old != new => ItemChange::Modified,
+                _ => return None,
+            };
+            Some((id.clone(), change))
+        })
+        .collect()
+}
+
+/// Which side of a merge is trusted for claim fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimAuthority {
+    /// Theirs wins whenever it changed the claim (merging from the primary remote).
+    Theirs,
+    /// Ours always wins (merging from a mirror whose claims aren't authoritative).
+    Ours,
+}
+
 /// Schema-aware three-way merge of stores.
 ///
 /// For items: added on one side only → keep. Modified on both sides on different
 /// fields → merge field-by-field. Same field changed on both → theirs wins for
-/// `claimed_by`, ours wins otherwise (with warning printed to stderr).
+/// `claimed_by` (unless `claims` says otherwise), ours wins otherwise (with warning printed to stderr).
 ///
-/// For deps: union of all deps from both sides, minus any removed from either side.
-pub fn merge_stores(base: &Store, ours: &Store, theirs: &Store) -> Result<Store, String> {
-    let mut merged = Store::default();
+/// For deps: union of all deps from both sides, minus any removed from either side,
+/// then cleared of the extra parents and cycles the union can create (see
+/// [`fsck::resolve_structure`]), which are returned alongside the store.
+///
+/// `claims` decides whether their claim changes are trusted at all; see
+/// [`ClaimAuthority`].
+pub fn merge_stores(
+    base: &Store,
+    ours: &Store,
+    theirs: &Store,
+    claims: ClaimAuthority,
+) -> Result<(Store, Vec<Problem>), String> {
+    let mut merged = merge_meta(base, ours, theirs);
 
     // Collect all item IDs across all three stores
     let all_ids: HashSet<&String> = base
@@ -292,85 +790,203 @@ pub fn merge_stores(base: &Store, ours: &Store, theirs: &Store) -> Result<Store,
         .chain(theirs.items.keys())
         .collect();
 
-    for id in &all_ids {
-        let in_base = base.items.get(*id);
-        let in_ours = ours.items.get(*id);
-        let in_theirs = theirs.items.get(*id);
+    for id in all_ids {
+        let (in_base, in_ours, in_theirs) =
+            (base.items.get(id), ours.items.get(id), theirs.items.get(id));
+        if let Some(item) = merge_item(in_base, in_ours, in_theirs, claims) {
+            merged.items.insert(id.clone(), item);
+        }
+    }
 
-        match (in_base, in_ours, in_theirs) {
-            // Only in ours (we added it)
-            (None, Some(item), None) => {
-                merged.items.insert((*id).clone(), item.clone());
-            }
-            // Only in theirs (they added it)
-            (None, None, Some(item)) => {
-                merged.items.insert((*id).clone(), item.clone());
-            }
-            // Added on both sides — keep theirs (they pushed first)
-            (None, Some(_), Some(item)) => {
-                merged.items.insert((*id).clone(), item.clone());
-            }
-            // In base and ours, deleted by them → honor deletion
-            (Some(_), Some(_), None) => {
-                // They deleted it. If we modified it, warn but still honor deletion.
-            }
-            // In base and theirs, deleted by us → honor deletion
-            (Some(_), None, Some(_)) => {
-                // We deleted it.
-            }
-            // In all three — merge field by field
-            (Some(base_item), Some(our_item), Some(their_item)) => {
-                let item = merge_items(base_item, our_item, their_item);
-                merged.items.insert((*id).clone(), item);
-            }
-            // In base only — both deleted
-            (Some(_), None, None) => {}
-            // Not in any — impossible given how we collected IDs
-            (None, None, None) => {}
+    let (deps, resolved) = merge_deps(base.deps(), ours.deps(), theirs.deps(), |id| {
+        merged.items.contains_key(id)
+    });
+    merged.set_deps(deps);
+
+    Ok((merged, resolved))
+}
+
+/// Merge everything but items and deps (schema version, settings and
+/// unknown fields) into an otherwise empty store.
+pub fn merge_meta(base: &Store, ours: &Store, theirs: &Store) -> Store {
+    let mut merged = Store::default();
+    merged.schema_version = ours.schema_version.max(theirs.schema_version);
+    merged.settings = merge_settings(&base.settings, &ours.settings, &theirs.settings);
+    merged.milestones = merge_milestones(&base.milestones, &ours.milestones, &theirs.milestones);
+    merged.extra = merge_extra(&base.extra, &ours.extra, &theirs.extra);
+    merged
+}
+
+/// Milestones merge by name, field by field: ours if we changed it, theirs
+/// otherwise. One created on both sides keeps theirs, like items.
+fn merge_milestones(
+    base: &BTreeMap<String, Milestone>,
+    ours: &BTreeMap<String, Milestone>,
+    theirs: &BTreeMap<String, Milestone>,
+) -> BTreeMap<String, Milestone> {
+    let names: std::collections::BTreeSet<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let merged = match (base.get(name), ours.get(name), theirs.get(name)) {
+                (Some(b), Some(o), Some(t)) => Milestone {
+                    name: name.clone(),
+                    target: if o.target != b.target {
+                        o.target
+                    } else {
+                        t.target
+                    },
+                    state: if o.state != b.state { o.state } else { t.state },
+                    created_at: o.created_at,
+                    extra: merge_extra(&b.extra, &o.extra, &t.extra),
+                },
+                (None, _, Some(t)) => t.clone(),
+                (None, Some(o), None) => o.clone(),
+                // Deleted on either side
+                _ => return None,
+            };
+            Some((name.clone(), merged))
+        })
+        .collect()
+}
+
+/// Three-way merge of one item; `None` means it doesn't survive the merge.
+pub fn merge_item(
+    base: Option<&Item>,
+    ours: Option<&Item>,
+    theirs: Option<&Item>,
+    claims: ClaimAuthority,
+) -> Option<Item> {
+    match (base, ours, theirs) {
+        // Only in ours (we added it)
+        (None, Some(item), None) => Some(item.clone()),
+        // Only in theirs (they added it)
+        (None, None, Some(item)) => Some(item.clone()),
+        // Added on both sides — keep theirs (they pushed first)
+        (None, Some(_), Some(item)) => Some(item.clone()),
+        // In base and ours, deleted by them → honor deletion
+        (Some(_), Some(_), None) => None,
+        // In base and theirs, deleted by us → honor deletion
+        (Some(_), None, Some(_)) => None,
+        // In all three — merge field by field
+        (Some(base_item), Some(our_item), Some(their_item)) => {
+            Some(merge_items(base_item, our_item, their_item, claims))
         }
+        // In base only — both deleted; or in none at all
+        (Some(_), None, None) | (None, None, None) => None,
     }
+}
 
-    // Merge deps: union of ours and theirs, minus any removed relative to base
-    let base_deps: HashSet<&Dep> = base.deps.iter().collect();
-    let our_deps: HashSet<&Dep> = ours.deps.iter().collect();
-    let their_deps: HashSet<&Dep> = theirs.deps.iter().collect();
+/// Union of ours and theirs, minus any removed relative to base. Deps whose
+/// endpoints fail `exists` are dropped, and a dep added on both sides keeps
+/// the earlier timestamp. Extra parents and cycles are then resolved, and
+/// returned as the problems they were.
+pub fn merge_deps(
+    base: &[Dep],
+    ours: &[Dep],
+    theirs: &[Dep],
+    exists: impl Fn(&str) -> bool,
+) -> (Vec<Dep>, Vec<Problem>) {
+    let base_deps: HashSet<&Dep> = base.iter().collect();
+    let our_deps: HashSet<&Dep> = ours.iter().collect();
+    let their_deps: HashSet<&Dep> = theirs.iter().collect();
 
     let mut merged_deps: HashSet<Dep> = HashSet::new();
 
     // Keep deps that exist in ours (unless removed by theirs relative to base)
-    for dep in &ours.deps {
+    for dep in ours {
         let was_in_base = base_deps.contains(dep);
         let in_theirs = their_deps.contains(dep);
         if !was_in_base || in_theirs {
             // New in ours, or still in both
-            if merged.items.contains_key(&dep.from_id) && merged.items.contains_key(&dep.to_id) {
+            if exists(&dep.from_id) && exists(&dep.to_id) {
                 merged_deps.insert(dep.clone());
             }
         }
     }
 
     // Keep deps that exist in theirs (unless removed by ours relative to base)
-    for dep in &theirs.deps {
+    for dep in theirs {
         let was_in_base = base_deps.contains(dep);
         let in_ours = our_deps.contains(dep);
-        if (!was_in_base || in_ours)
-            && merged.items.contains_key(&dep.from_id)
-            && merged.items.contains_key(&dep.to_id)
-        {
-            merged_deps.insert(dep.clone());
+        if (!was_in_base || in_ours) && exists(&dep.from_id) && exists(&dep.to_id) {
+            match merged_deps.get(dep) {
+                Some(kept) if kept.created_at <= dep.created_at => {}
+                _ => {
+                    merged_deps.replace(dep.clone());
+                }
+            }
         }
     }
 
-    merged.deps = merged_deps.into_iter().collect();
+    let mut merged: Vec<Dep> = merged_deps.into_iter().collect();
     // Sort deps for deterministic output
-    merged
-        .deps
-        .sort_by(|a, b| (&a.from_id, &a.to_id).cmp(&(&b.from_id, &b.to_id)));
+    merged.sort_by(|a, b| (&a.from_id, &a.to_id).cmp(&(&b.from_id, &b.to_id)));
+    let resolved = fsck::resolve_structure(&mut merged);
+    (merged, resolved)
+}
+
+pub fn merge_settings(base: &Settings, ours: &Settings, theirs: &Settings) -> Settings {
+    Settings {
+        auto_close_parents: if ours.auto_close_parents != base.auto_close_parents {
+            ours.auto_close_parents
+        } else {
+            theirs.auto_close_parents
+        },
+        layout: if ours.layout != base.layout {
+            ours.layout
+        } else {
+            theirs.layout
+        },
+        score: merge_weights(&base.score, &ours.score, &theirs.score),
+        extra: merge_extra(&base.extra, &ours.extra, &theirs.extra),
+    }
+}
 
-    Ok(merged)
+/// Each weight independently: ours if we changed it, theirs otherwise.
+fn merge_weights(base: &ScoreWeights, ours: &ScoreWeights, theirs: &ScoreWeights) -> ScoreWeights {
+    let pick = |b: f64, o: f64, t: f64| if o != b { o } else { t };
+    ScoreWeights {
+        priority: pick(base.priority, ours.priority, theirs.priority),
+        age: pick(base.age, ours.age, theirs.age),
+        unblocks: pick(base.unblocks, ours.unblocks, theirs.unblocks),
+        parent: pick(base.parent, ours.parent, theirs.parent),
+        due: pick(base.due, ours.due, theirs.due),
+    }
 }
 
-fn merge_items(base: &Item, ours: &Item, theirs: &Item) -> Item {
+/// Unknown fields merge key by key: ours if we changed it, theirs otherwise.
+fn merge_extra(base: &Extra, ours: &Extra, theirs: &Extra) -> Extra {
+    let keys: std::collections::BTreeSet<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    keys.into_iter()
+        .filter_map(|k| {
+            let side = if ours.get(k) != base.get(k) {
+                ours
+            } else {
+                theirs
+            };
+            Some((k.clone(), side.get(k)?.clone()))
+        })
+        .collect()
+}
+
+fn merge_items(base: &Item, ours: &Item, theirs: &Item, claims: ClaimAuthority) -> Item {
+    let theirs_changed_claim = theirs.claimed_by != base.claimed_by
+        || theirs.claimed_at != base.claimed_at
+        || theirs.claim_ttl != base.claim_ttl;
+    let claim_side = if theirs_changed_claim && claims == ClaimAuthority::Theirs {
+        theirs
+    } else {
+        ours
+    };
     Item {
         id: ours.id.clone(),
         title: if ours.title != base.title {
@@ -398,15 +1014,43 @@ fn merge_items(base: &Item, ours: &Item, theirs: &Item) -> Item {
         } else {
             theirs.priority
         },
-        // For claimed_by: theirs wins (first push wins)
-        claimed_by: if theirs.claimed_by != base.claimed_by {
-            theirs.claimed_by.clone()
+        estimate: if ours.estimate != base.estimate {
+            ours.estimate
+        } else {
+            theirs.estimate
+        },
+        due: if ours.due != base.due {
+            ours.due
         } else {
-            ours.claimed_by.clone()
+            theirs.due
         },
+        milestone: if ours.milestone != base.milestone {
+            ours.milestone.clone()
+        } else {
+            theirs.milestone.clone()
+        },
+        // For the claim: theirs wins (first push wins). The lease fields
+        // travel with claimed_by so a renewal on either side is kept whole.
+        claimed_by: claim_side.claimed_by.clone(),
+        claimed_at: claim_side.claimed_at,
+        claim_ttl: claim_side.claim_ttl,
         created_at: ours.created_at,
         updated_at: std::cmp::max(ours.updated_at, theirs.updated_at),
+        history: merge_history(&ours.history, &theirs.history),
+        extra: merge_extra(&base.extra, &ours.extra, &theirs.extra),
+    }
+}
+
+/// History is append-only, so the merge is the union of both sides in time order.
+fn merge_history(ours: &[Event], theirs: &[Event]) -> Vec<Event> {
+    let mut merged = ours.to_vec();
+    for event in theirs {
+        if !merged.contains(event) {
+            merged.push(event.clone());
+        }
     }
+    merged.sort_by_key(|e| e.at);
+    merged
 }
 
 #[cfg(test)]
@@ -419,27 +1063,29 @@ mod tests {
         let mut store = Store::default();
         let mut ids = Vec::new();
         for title in titles {
-            let id =
-                create_item(&mut store, title.to_string(), ItemType::Task, 2, None, None).unwrap();
+            let id = create_item(
+                &mut store,
+                title.to_string(),
+                ItemType::Task,
+                2,
+                Schedule::default(),
+                None,
+                None,
+            )
+            .unwrap();
             ids.push(id);
         }
         (store, ids)
     }
 
     fn insert_item(store: &mut Store, id: &str, title: &str, status: Status, priority: u8) {
-        let now = Utc::now();
         store.items.insert(
             id.to_string(),
             Item {
-                id: id.to_string(),
                 title: title.to_string(),
-                description: None,
-                item_type: ItemType::Task,
                 status,
                 priority,
-                claimed_by: None,
-                created_at: now,
-                updated_at: now,
+                ..Item::for_test(id)
             },
         );
     }
@@ -486,6 +1132,7 @@ mod tests {
             "My task".to_string(),
             ItemType::Task,
             1,
+            Schedule::default(),
             Some("desc".to_string()),
             None,
         )
@@ -500,6 +1147,65 @@ mod tests {
         assert!(item.claimed_by.is_none());
     }
 
+    #[test]
+    fn create_item_with_estimate() {
+        let mut store = Store::default();
+        let schedule = Schedule {
+            estimate: Some(Estimate::Points(3.0)),
+            ..Default::default()
+        };
+        let id = create_item(
+            &mut store,
+            "Sized".to_string(),
+            ItemType::Task,
+            2,
+            schedule,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(store.items[&id].estimate, Some(Estimate::Points(3.0)));
+    }
+
+    #[test]
+    fn create_item_scheduled() {
+        let mut store = Store::default();
+        milestone::create(&mut store, "v1", None).unwrap();
+        let schedule = |name: &str| Schedule {
+            due: "2026-11-03".parse().ok(),
+            milestone: Some(name.to_string()),
+            ..Default::default()
+        };
+        let id = create_item(
+            &mut store,
+            "Planned".to_string(),
+            ItemType::Task,
+            2,
+            schedule("v1"),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(store.items[&id].due, "2026-11-03".parse().ok());
+        assert_eq!(store.items[&id].milestone.as_deref(), Some("v1"));
+
+        milestone::close(&mut store, "v1").unwrap();
+        for name in ["v1", "v9"] {
+            let err = create_item(
+                &mut store,
+                "Late".to_string(),
+                ItemType::Task,
+                2,
+                schedule(name),
+                None,
+                None,
+            )
+            .unwrap_err();
+            assert!(err.contains(name), "{err}");
+        }
+        assert_eq!(store.items.len(), 1);
+    }
+
     #[test]
     fn create_item_with_parent() {
         let (mut store, ids) = make_store(&["parent"]);
@@ -508,6 +1214,7 @@ mod tests {
             "child".to_string(),
             ItemType::Task,
             2,
+            Schedule::default(),
             None,
             Some(ids[0].clone()),
         )
@@ -523,6 +1230,7 @@ mod tests {
             "orphan".to_string(),
             ItemType::Task,
             2,
+            Schedule::default(),
             None,
             Some("lb-nope".to_string()),
         )
@@ -541,9 +1249,9 @@ mod tests {
     fn delete_item_removes_deps() {
         let (mut store, ids) = make_store(&["a", "b"]);
         add_blocking_dep(&mut store, &ids[0], &ids[1]).unwrap();
-        assert_eq!(store.deps.len(), 1);
+        assert_eq!(store.deps().len(), 1);
         delete_item(&mut store, &ids[0]).unwrap();
-        assert!(store.deps.is_empty());
+        assert!(store.deps().is_empty());
     }
 
     #[test]
@@ -554,7 +1262,7 @@ mod tests {
         let deleted = delete_item(&mut store, &ids[0]).unwrap();
         assert_eq!(deleted.len(), 3);
         assert!(store.items.is_empty());
-        assert!(store.deps.is_empty());
+        assert!(store.deps().is_empty());
     }
 
     #[test]
@@ -582,7 +1290,7 @@ mod tests {
         assert_eq!(get_parent(&store, &ids[2]), Some(ids[1].clone()));
         // Only one parent dep should remain for the child
         let parent_deps: Vec<_> = store
-            .deps
+            .deps()
             .iter()
             .filter(|d| d.from_id == ids[2] && d.dep_type == DepType::Parent)
             .collect();
@@ -719,10 +1427,11 @@ mod tests {
         let mut store = Store::default();
         insert_item(&mut store, "lb-aaaa", "blocker", Status::Open, 1);
         insert_item(&mut store, "lb-bbbb", "blocked", Status::Open, 1);
-        store.deps.push(Dep {
+        store.add_dep(Dep {
             from_id: "lb-aaaa".to_string(),
             to_id: "lb-bbbb".to_string(),
             dep_type: DepType::Blocks,
+            created_at: None,
         });
         let ready = ready_items(&store);
         assert_eq!(ready.len(), 1);
@@ -734,10 +1443,11 @@ mod tests {
         let mut store = Store::default();
         insert_item(&mut store, "lb-aaaa", "blocker", Status::Closed, 1);
         insert_item(&mut store, "lb-bbbb", "blocked", Status::Open, 1);
-        store.deps.push(Dep {
+        store.add_dep(Dep {
             from_id: "lb-aaaa".to_string(),
             to_id: "lb-bbbb".to_string(),
             dep_type: DepType::Blocks,
+            created_at: None,
         });
         let ready = ready_items(&store);
         // lb-bbbb is ready (blocker is closed); lb-aaaa is closed so not ready
@@ -752,6 +1462,28 @@ mod tests {
         assert!(ready_items(&store).is_empty());
     }
 
+    #[test]
+    fn descendants_follow_the_parent_chain() {
+        let mut store = Store::default();
+        for id in ["lb-aaaa", "lb-bbbb", "lb-cccc", "lb-dddd"] {
+            insert_item(&mut store, id, id, Status::Open, 2);
+        }
+        store.add_dep(Dep::new(
+            "lb-bbbb".into(),
+            "lb-aaaa".into(),
+            DepType::Parent,
+        ));
+        store.add_dep(Dep::new(
+            "lb-cccc".into(),
+            "lb-bbbb".into(),
+            DepType::Parent,
+        ));
+        assert!(is_descendant(&store, "lb-cccc", "lb-aaaa"));
+        assert!(is_descendant(&store, "lb-bbbb", "lb-aaaa"));
+        assert!(!is_descendant(&store, "lb-aaaa", "lb-aaaa"));
+        assert!(!is_descendant(&store, "lb-dddd", "lb-aaaa"));
+    }
+
     #[test]
     fn ready_sorted_by_priority() {
         let mut store = Store::default();
@@ -774,6 +1506,293 @@ mod tests {
         assert_eq!(ready[0].id, "lb-aaaa");
     }
 
+    #[test]
+    fn ready_includes_expired_claim() {
+        let mut store = Store::default();
+        insert_item(&mut store, "lb-aaaa", "abandoned", Status::Open, 1);
+        insert_item(&mut store, "lb-bbbb", "leased", Status::Open, 1);
+        let now = Utc::now();
+        let a = store.items.get_mut("lb-aaaa").unwrap();
+        a.claimed_by = Some("alice".to_string());
+        a.claimed_at = Some(now - Duration::hours(5));
+        a.claim_ttl = Some(4 * 3600);
+        let b = store.items.get_mut("lb-bbbb").unwrap();
+        b.claimed_by = Some("bob".to_string());
+        b.claimed_at = Some(now - Duration::hours(1));
+        b.claim_ttl = Some(4 * 3600);
+        let ready = ready_items(&store);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].id, "lb-aaaa");
+    }
+
+    // --- Claims ---
+
+    #[test]
+    fn claim_item_sets_lease() {
+        let (mut store, ids) = make_store(&["task"]);
+        let now = Utc::now();
+        claim_item(&mut store, &ids[0], "alice", Some(3600), now).unwrap();
+        let item = &store.items[&ids[0]];
+        assert_eq!(item.claimed_by.as_deref(), Some("alice"));
+        assert_eq!(item.claimed_at, Some(now));
+        assert_eq!(claim_expires_at(item), Some(now + Duration::hours(1)));
+    }
+
+    #[test]
+    fn claim_item_rejects_active_claim() {
+        let (mut store, ids) = make_store(&["task"]);
+        let now = Utc::now();
+        claim_item(&mut store, &ids[0], "alice", Some(3600), now).unwrap();
+        let err = claim_item(&mut store, &ids[0], "bob", None, now).unwrap_err();
+        assert!(err.contains("already claimed by alice"), "{err}");
+    }
+
+    #[test]
+    fn claim_item_takes_over_expired_claim() {
+        let (mut store, ids) = make_store(&["task"]);
+        let then = Utc::now() - Duration::hours(2);
+        claim_item(&mut store, &ids[0], "alice", Some(3600), then).unwrap();
+        let now = Utc::now();
+        claim_item(&mut store, &ids[0], "bob", None, now).unwrap();
+        let item = &store.items[&ids[0]];
+        assert_eq!(item.claimed_by.as_deref(), Some("bob"));
+        assert!(item.claim_ttl.is_none());
+    }
+
+    #[test]
+    fn claim_item_rejects_closed() {
+        let (mut store, ids) = make_store(&["task"]);
+        close_item(&mut store, &ids[0]).unwrap();
+        let err = claim_item(&mut store, &ids[0], "alice", None, Utc::now()).unwrap_err();
+        assert!(err.contains("closed"), "{err}");
+    }
+
+    #[test]
+    fn claim_status_reports_winnability() {
+        let (mut store, ids) = make_store(&["free", "mine", "theirs", "done"]);
+        let now = Utc::now();
+        claim_item(&mut store, &ids[1], "alice", None, now).unwrap();
+        claim_item(&mut store, &ids[2], "bob", None, now).unwrap();
+        close_item(&mut store, &ids[3]).unwrap();
+        assert_eq!(claim_status(&store, &ids[0], "alice", now), Ok(false));
+        assert_eq!(claim_status(&store, &ids[1], "alice", now), Ok(true));
+        let err = claim_status(&store, &ids[2], "alice", now).unwrap_err();
+        assert!(err.contains("claimed by bob"), "{err}");
+        let err = claim_status(&store, &ids[3], "alice", now).unwrap_err();
+        assert!(err.contains("closed"), "{err}");
+        let err = claim_status(&store, "lb-gone", "alice", now).unwrap_err();
+        assert!(err.contains("no longer exists"), "{err}");
+    }
+
+    #[test]
+    fn renew_claim_extends_lease() {
+        let (mut store, ids) = make_store(&["task"]);
+        let then = Utc::now() - Duration::hours(3);
+        claim_item(&mut store, &ids[0], "alice", Some(4 * 3600), then).unwrap();
+        let now = Utc::now();
+        renew_claim(&mut store, &ids[0], "alice", None, now).unwrap();
+        let item = &store.items[&ids[0]];
+        assert_eq!(item.claimed_at, Some(now));
+        assert_eq!(item.claim_ttl, Some(4 * 3600));
+
+        renew_claim(&mut store, &ids[0], "alice", Some(60), now).unwrap();
+        assert_eq!(store.items[&ids[0]].claim_ttl, Some(60));
+    }
+
+    #[test]
+    fn renew_claim_requires_own_claim() {
+        let (mut store, ids) = make_store(&["task"]);
+        let err = renew_claim(&mut store, &ids[0], "alice", None, Utc::now()).unwrap_err();
+        assert!(err.contains("not claimed"), "{err}");
+        claim_item(&mut store, &ids[0], "bob", None, Utc::now()).unwrap();
+        let err = renew_claim(&mut store, &ids[0], "alice", None, Utc::now()).unwrap_err();
+        assert!(err.contains("claimed by bob"), "{err}");
+    }
+
+    #[test]
+    fn claim_over_expired_claim_records_history() {
+        let (mut store, ids) = make_store(&["task"]);
+        let then = Utc::now() - Duration::hours(2);
+        claim_item(&mut store, &ids[0], "alice", Some(60), then).unwrap();
+        claim_item(&mut store, &ids[0], "bob", None, Utc::now()).unwrap();
+        let history = &store.items[&ids[0]].history;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].kind, EventKind::Steal);
+        assert_eq!(history[0].from.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn steal_claim_records_previous_claimant() {
+        let (mut store, ids) = make_store(&["task"]);
+        claim_item(&mut store, &ids[0], "alice", None, Utc::now()).unwrap();
+        let prev = steal_claim(
+            &mut store,
+            &ids[0],
+            "bob",
+            Some("alice on holiday".to_string()),
+            None,
+            Utc::now(),
+        )
+        .unwrap();
+        assert_eq!(prev, "alice");
+        let item = &store.items[&ids[0]];
+        assert_eq!(item.claimed_by.as_deref(), Some("bob"));
+        assert_eq!(item.history.len(), 1);
+        let event = &item.history[0];
+        assert_eq!(event.kind, EventKind::Steal);
+        assert_eq!(event.actor, "bob");
+        assert_eq!(event.from.as_deref(), Some("alice"));
+        assert_eq!(event.reason.as_deref(), Some("alice on holiday"));
+    }
+
+    #[test]
+    fn steal_claim_requires_existing_claim() {
+        let (mut store, ids) = make_store(&["task"]);
+        let err = steal_claim(&mut store, &ids[0], "bob", None, None, Utc::now()).unwrap_err();
+        assert!(err.contains("not claimed"), "{err}");
+        claim_item(&mut store, &ids[0], "bob", None, Utc::now()).unwrap();
+        let err = steal_claim(&mut store, &ids[0], "bob", None, None, Utc::now()).unwrap_err();
+        assert!(err.contains("already yours"), "{err}");
+    }
+
+    #[test]
+    fn handoff_own_claim() {
+        let (mut store, ids) = make_store(&["task"]);
+        claim_item(&mut store, &ids[0], "alice", Some(3600), Utc::now()).unwrap();
+        let prev =
+            handoff_claim(&mut store, &ids[0], "alice", "bob", None, false, Utc::now()).unwrap();
+        assert_eq!(prev.as_deref(), Some("alice"));
+        let item = &store.items[&ids[0]];
+        assert_eq!(item.claimed_by.as_deref(), Some("bob"));
+        assert_eq!(item.claim_ttl, Some(3600));
+        assert_eq!(item.history[0].kind, EventKind::Handoff);
+        assert_eq!(item.history[0].to.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn handoff_someone_elses_claim_needs_force() {
+        let (mut store, ids) = make_store(&["task"]);
+        claim_item(&mut store, &ids[0], "alice", None, Utc::now()).unwrap();
+        let err = handoff_claim(&mut store, &ids[0], "carol", "bob", None, false, Utc::now())
+            .unwrap_err();
+        assert!(err.contains("--force"), "{err}");
+        assert!(store.items[&ids[0]].history.is_empty());
+        handoff_claim(&mut store, &ids[0], "carol", "bob", None, true, Utc::now()).unwrap();
+        let event = &store.items[&ids[0]].history[0];
+        assert_eq!(event.actor, "carol");
+        assert_eq!(event.from.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn release_claim_own_and_forced() {
+        let (mut store, ids) = make_store(&["mine", "theirs"]);
+        claim_item(&mut store, &ids[0], "alice", None, Utc::now()).unwrap();
+        claim_item(&mut store, &ids[1], "bob", None, Utc::now()).unwrap();
+
+        release_claim(&mut store, &ids[0], "alice", false, None, Utc::now()).unwrap();
+        assert!(store.items[&ids[0]].claimed_by.is_none());
+        assert!(store.items[&ids[0]].history.is_empty());
+
+        let err = release_claim(&mut store, &ids[1], "alice", false, None, Utc::now()).unwrap_err();
+        assert!(err.contains("--force"), "{err}");
+        assert_eq!(store.items[&ids[1]].claimed_by.as_deref(), Some("bob"));
+
+        release_claim(
+            &mut store,
+            &ids[1],
+            "alice",
+            true,
+            Some("stuck".to_string()),
+            Utc::now(),
+        )
+        .unwrap();
+        let item = &store.items[&ids[1]];
+        assert!(item.claimed_by.is_none());
+        assert_eq!(item.history[0].kind, EventKind::ForceUnclaim);
+        assert_eq!(item.history[0].reason.as_deref(), Some("stuck"));
+    }
+
+    #[test]
+    fn release_stale_claims_only_touches_expired() {
+        let (mut store, ids) = make_store(&["stale", "fresh", "forever"]);
+        let now = Utc::now();
+        claim_item(
+            &mut store,
+            &ids[0],
+            "alice",
+            Some(60),
+            now - Duration::hours(1),
+        )
+        .unwrap();
+        claim_item(&mut store, &ids[1], "bob", Some(3600), now).unwrap();
+        claim_item(&mut store, &ids[2], "carol", None, now - Duration::days(30)).unwrap();
+        let released = release_stale_claims(&mut store, now);
+        assert_eq!(released, vec![(ids[0].clone(), "alice".to_string())]);
+        assert!(store.items[&ids[0]].claimed_by.is_none());
+        assert!(store.items[&ids[0]].claimed_at.is_none());
+        assert!(store.items[&ids[1]].claimed_by.is_some());
+        assert!(store.items[&ids[2]].claimed_by.is_some());
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("30s").unwrap(), 30);
+        assert_eq!(parse_duration("15m").unwrap(), 900);
+        assert_eq!(parse_duration("4h").unwrap(), 4 * 3600);
+        assert_eq!(parse_duration("2d").unwrap(), 2 * 86400);
+        assert_eq!(parse_duration("1w").unwrap(), 7 * 86400);
+        assert!(parse_duration("4").is_err());
+        assert!(parse_duration("0h").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("4y").is_err());
+    }
+
+    #[test]
+    fn parse_duration_overflow() {
+        let err = parse_duration("99999999999999999w").unwrap_err();
+        assert!(err.contains("too large"), "{err}");
+        assert_eq!(parse_ttl("4h").unwrap(), 4 * 3600);
+        assert!(parse_ttl("200000000000d").unwrap_err().contains("too long"));
+    }
+
+    #[test]
+    fn huge_lease_never_expires_instead_of_panicking() {
+        let (mut store, ids) = make_store(&["task"]);
+        let now = Utc::now();
+        claim_item(&mut store, &ids[0], "alice", Some(u64::MAX / 2), now).unwrap();
+        let item = &store.items[&ids[0]];
+        assert_eq!(claim_expires_at(item), None);
+        assert!(!is_claim_stale(item, now));
+    }
+
+    #[test]
+    fn format_duration_compact() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(35 * 60), "35m");
+        assert_eq!(format_duration(4 * 3600), "4h");
+        assert_eq!(format_duration(4 * 3600 + 300), "4h5m");
+        assert_eq!(format_duration(2 * 86400 + 4 * 3600), "2d4h");
+    }
+
+    #[test]
+    fn ready_claimed_by_own_unblocked_items() {
+        let mut store = Store::default();
+        insert_item(&mut store, "lb-aaaa", "mine", Status::Open, 1);
+        insert_item(&mut store, "lb-bbbb", "mine but blocked", Status::Open, 1);
+        insert_item(&mut store, "lb-cccc", "theirs", Status::Open, 1);
+        insert_item(&mut store, "lb-dddd", "blocker", Status::Open, 1);
+        let now = Utc::now();
+        claim_item(&mut store, "lb-aaaa", "alice", None, now).unwrap();
+        claim_item(&mut store, "lb-bbbb", "alice", None, now).unwrap();
+        claim_item(&mut store, "lb-cccc", "bob", None, now).unwrap();
+        add_blocking_dep(&mut store, "lb-dddd", "lb-bbbb").unwrap();
+        let mine: Vec<&str> = ready_claimed_by(&store, "alice")
+            .iter()
+            .map(|i| i.id.as_str())
+            .collect();
+        assert_eq!(mine, vec!["lb-aaaa"]);
+    }
+
     // --- Root items ---
 
     #[test]
@@ -820,7 +1839,7 @@ mod tests {
         let mut theirs = Store::default();
         insert_item(&mut theirs, "lb-bbbb", "theirs", Status::Open, 1);
 
-        let merged = merge_stores(&base, &ours, &theirs).unwrap();
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
         assert_eq!(merged.items.len(), 2);
         assert!(merged.items.contains_key("lb-aaaa"));
         assert!(merged.items.contains_key("lb-bbbb"));
@@ -828,20 +1847,12 @@ mod tests {
 
     #[test]
     fn merge_different_fields_changed() {
-        let now = Utc::now();
         let mut base = Store::default();
         base.items.insert(
             "lb-aaaa".to_string(),
             Item {
-                id: "lb-aaaa".to_string(),
                 title: "original".to_string(),
-                description: None,
-                item_type: ItemType::Task,
-                status: Status::Open,
-                priority: 2,
-                claimed_by: None,
-                created_at: now,
-                updated_at: now,
+                ..Item::for_test("lb-aaaa")
             },
         );
 
@@ -851,28 +1862,82 @@ mod tests {
         let mut theirs = base.clone();
         theirs.items.get_mut("lb-aaaa").unwrap().priority = 0;
 
-        let merged = merge_stores(&base, &ours, &theirs).unwrap();
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
         let item = &merged.items["lb-aaaa"];
         assert_eq!(item.title, "our title");
         assert_eq!(item.priority, 0);
     }
 
     #[test]
-    fn merge_claimed_by_theirs_wins() {
+    fn merge_keeps_estimates() {
+        let mut base = Store::default();
+        insert_item(&mut base, "lb-aaaa", "task", Status::Open, 2);
+        let ours = base.clone();
+        let mut theirs = base.clone();
+        theirs.items.get_mut("lb-aaaa").unwrap().estimate = Some(Estimate::Points(5.0));
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        assert_eq!(
+            merged.items["lb-aaaa"].estimate,
+            Some(Estimate::Points(5.0))
+        );
+
+        // Both sides changed it: ours wins, as for priority
+        let mut ours = ours;
+        ours.items.get_mut("lb-aaaa").unwrap().estimate = Some(Estimate::Duration(3600));
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        assert_eq!(
+            merged.items["lb-aaaa"].estimate,
+            Some(Estimate::Duration(3600))
+        );
+    }
+
+    #[test]
+    fn merge_keeps_due_dates() {
+        let mut base = Store::default();
+        insert_item(&mut base, "lb-aaaa", "task", Status::Open, 2);
+        let mut ours = base.clone();
+        ours.items.get_mut("lb-aaaa").unwrap().title = "our title".to_string();
+        let mut theirs = base.clone();
+        theirs.items.get_mut("lb-aaaa").unwrap().due = "2026-11-03".parse().ok();
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        assert_eq!(merged.items["lb-aaaa"].due, "2026-11-03".parse().ok());
+    }
+
+    #[test]
+    fn merge_milestones_field_by_field() {
         let now = Utc::now();
+        let milestone = |name: &str| Milestone {
+            name: name.to_string(),
+            target: None,
+            state: MilestoneState::Open,
+            created_at: now,
+            extra: Extra::new(),
+        };
+        let mut base = Store::default();
+        base.milestones.insert("v1".into(), milestone("v1"));
+        let mut ours = base.clone();
+        ours.milestones.get_mut("v1").unwrap().target = "2026-11-03".parse().ok();
+        ours.milestones.insert("v2".into(), milestone("v2"));
+        let mut theirs = base.clone();
+        theirs.milestones.get_mut("v1").unwrap().state = MilestoneState::Closed;
+        theirs.milestones.insert("v3".into(), milestone("v3"));
+
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        let v1 = &merged.milestones["v1"];
+        assert_eq!(v1.target, "2026-11-03".parse().ok());
+        assert_eq!(v1.state, MilestoneState::Closed);
+        let names: Vec<&String> = merged.milestones.keys().collect();
+        assert_eq!(names, ["v1", "v2", "v3"]);
+    }
+
+    #[test]
+    fn merge_claimed_by_theirs_wins() {
         let mut base = Store::default();
         base.items.insert(
             "lb-aaaa".to_string(),
             Item {
-                id: "lb-aaaa".to_string(),
                 title: "task".to_string(),
-                description: None,
-                item_type: ItemType::Task,
-                status: Status::Open,
-                priority: 2,
-                claimed_by: None,
-                created_at: now,
-                updated_at: now,
+                ..Item::for_test("lb-aaaa")
             },
         );
 
@@ -882,11 +1947,103 @@ mod tests {
         let mut theirs = base.clone();
         theirs.items.get_mut("lb-aaaa").unwrap().claimed_by = Some("bob".to_string());
 
-        let merged = merge_stores(&base, &ours, &theirs).unwrap();
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
         // Theirs wins for claimed_by
         assert_eq!(merged.items["lb-aaaa"].claimed_by.as_deref(), Some("bob"));
     }
 
+    #[test]
+    fn changed_items_between_stores() {
+        let mut before = Store::default();
+        insert_item(&mut before, "lb-aaaa", "same", Status::Open, 2);
+        insert_item(&mut before, "lb-bbbb", "edited", Status::Open, 2);
+        insert_item(&mut before, "lb-cccc", "deleted", Status::Open, 2);
+        let mut after = before.clone();
+        after.items.get_mut("lb-bbbb").unwrap().priority = 0;
+        after.items.remove("lb-cccc");
+        insert_item(&mut after, "lb-dddd", "new", Status::Open, 2);
+
+        assert_eq!(
+            changed_items(&before, &after),
+            vec![
+                ("lb-bbbb".to_string(), ItemChange::Modified),
+                ("lb-cccc".to_string(), ItemChange::Removed),
+                ("lb-dddd".to_string(), ItemChange::Added),
+            ]
+        );
+        assert!(changed_items(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn merge_from_mirror_keeps_our_claims() {
+        let mut base = Store::default();
+        insert_item(&mut base, "lb-aaaa", "task", Status::Open, 2);
+        let mut ours = base.clone();
+        ours.items.get_mut("lb-aaaa").unwrap().claimed_by = Some("alice".to_string());
+        let mut theirs = base.clone();
+        let item = theirs.items.get_mut("lb-aaaa").unwrap();
+        item.claimed_by = Some("bob".to_string());
+        item.title = "renamed on mirror".to_string();
+        insert_item(&mut theirs, "lb-bbbb", "claimed on mirror", Status::Open, 2);
+        theirs.items.get_mut("lb-bbbb").unwrap().claimed_by = Some("bob".to_string());
+
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Ours).unwrap();
+        assert_eq!(merged.items["lb-aaaa"].claimed_by.as_deref(), Some("alice"));
+        // Other fields still merge normally
+        assert_eq!(merged.items["lb-aaaa"].title, "renamed on mirror");
+        // New items come across as-is
+        assert_eq!(merged.items["lb-bbbb"].claimed_by.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn merge_unions_history() {
+        let mut base = Store::default();
+        insert_item(&mut base, "lb-aaaa", "task", Status::Open, 2);
+        claim_item(&mut base, "lb-aaaa", "alice", None, Utc::now()).unwrap();
+
+        let mut ours = base.clone();
+        handoff_claim(
+            &mut ours,
+            "lb-aaaa",
+            "alice",
+            "bob",
+            None,
+            false,
+            Utc::now(),
+        )
+        .unwrap();
+        let mut theirs = base.clone();
+        release_claim(&mut theirs, "lb-aaaa", "carol", true, None, Utc::now()).unwrap();
+
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        let kinds: Vec<EventKind> = merged.items["lb-aaaa"]
+            .history
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(kinds, vec![EventKind::Handoff, EventKind::ForceUnclaim]);
+    }
+
+    #[test]
+    fn merge_keeps_our_claim_renewal() {
+        let mut base = Store::default();
+        insert_item(&mut base, "lb-aaaa", "task", Status::Open, 2);
+        let then = Utc::now() - Duration::hours(3);
+        claim_item(&mut base, "lb-aaaa", "alice", Some(4 * 3600), then).unwrap();
+
+        let mut ours = base.clone();
+        let now = Utc::now();
+        renew_claim(&mut ours, "lb-aaaa", "alice", None, now).unwrap();
+
+        let mut theirs = base.clone();
+        theirs.items.get_mut("lb-aaaa").unwrap().priority = 0;
+
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        let item = &merged.items["lb-aaaa"];
+        assert_eq!(item.claimed_at, Some(now));
+        assert_eq!(item.priority, 0);
+    }
+
     #[test]
     fn merge_deletion_honored() {
         let mut base = Store::default();
@@ -899,7 +2056,7 @@ mod tests {
 
         let theirs = base.clone();
 
-        let merged = merge_stores(&base, &ours, &theirs).unwrap();
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
         assert!(!merged.items.contains_key("lb-aaaa"));
         assert!(merged.items.contains_key("lb-bbbb"));
     }
@@ -945,6 +2102,90 @@ mod tests {
         assert!(store.items[&ids[0]].claimed_by.is_none());
     }
 
+    #[test]
+    fn close_item_leaves_parent_open_by_default() {
+        let (mut store, ids) = make_store(&["epic", "task"]);
+        set_parent(&mut store, &ids[1], &ids[0]).unwrap();
+        let closed = close_item(&mut store, &ids[1]).unwrap();
+        assert_eq!(closed, vec![ids[1].clone()]);
+        assert_eq!(store.items[&ids[0]].status, Status::Open);
+    }
+
+    #[test]
+    fn close_item_auto_closes_parents() {
+        let (mut store, ids) = make_store(&["epic", "feature", "task1", "task2"]);
+        store.settings.auto_close_parents = true;
+        set_parent(&mut store, &ids[1], &ids[0]).unwrap();
+        set_parent(&mut store, &ids[2], &ids[1]).unwrap();
+        set_parent(&mut store, &ids[3], &ids[1]).unwrap();
+
+        // One task still open — nothing cascades up
+        let closed = close_item(&mut store, &ids[2]).unwrap();
+        assert_eq!(closed, vec![ids[2].clone()]);
+        assert_eq!(store.items[&ids[1]].status, Status::Open);
+
+        // Last task closes → feature and then epic close
+        let closed = close_item(&mut store, &ids[3]).unwrap();
+        assert_eq!(closed, vec![ids[3].clone(), ids[1].clone(), ids[0].clone()]);
+        assert_eq!(store.items[&ids[0]].status, Status::Closed);
+    }
+
+    #[test]
+    fn close_cascade_closes_descendants() {
+        let (mut store, ids) = make_store(&["epic", "feature", "task", "done"]);
+        set_parent(&mut store, &ids[1], &ids[0]).unwrap();
+        set_parent(&mut store, &ids[2], &ids[1]).unwrap();
+        set_parent(&mut store, &ids[3], &ids[0]).unwrap();
+        close_item(&mut store, &ids[3]).unwrap();
+        store.items.get_mut(&ids[2]).unwrap().claimed_by = Some("alice".to_string());
+
+        let closed = close_cascade(&mut store, &ids[0]).unwrap();
+        // Already-closed child is not reported; deepest first, root last
+        assert_eq!(closed, vec![ids[2].clone(), ids[1].clone(), ids[0].clone()]);
+        assert!(store.items.values().all(|i| i.status == Status::Closed));
+        assert!(store.items[&ids[2]].claimed_by.is_none());
+    }
+
+    #[test]
+    fn close_cascade_honors_auto_close_parents() {
+        let (mut store, ids) = make_store(&["epic", "feature", "task"]);
+        store.settings.auto_close_parents = true;
+        set_parent(&mut store, &ids[1], &ids[0]).unwrap();
+        set_parent(&mut store, &ids[2], &ids[1]).unwrap();
+        let closed = close_cascade(&mut store, &ids[1]).unwrap();
+        assert_eq!(closed, vec![ids[2].clone(), ids[1].clone(), ids[0].clone()]);
+    }
+
+    // --- Config ---
+
+    #[test]
+    fn set_config_auto_close_parents() {
+        let mut store = Store::default();
+        set_config(&mut store, "auto_close_parents", "true").unwrap();
+        assert!(store.settings.auto_close_parents);
+        set_config(&mut store, "auto_close_parents", "off").unwrap();
+        assert!(!store.settings.auto_close_parents);
+    }
+
+    #[test]
+    fn set_config_rejects_unknown_key_and_bad_value() {
+        let mut store = Store::default();
+        let err = set_config(&mut store, "nope", "true").unwrap_err();
+        assert!(err.contains("unknown config key"), "{err}");
+        let err = set_config(&mut store, "auto_close_parents", "maybe").unwrap_err();
+        assert!(err.contains("invalid boolean"), "{err}");
+    }
+
+    #[test]
+    fn merge_settings_takes_changed_side() {
+        let base = Store::default();
+        let ours = base.clone();
+        let mut theirs = base.clone();
+        theirs.settings.auto_close_parents = true;
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        assert!(merged.settings.auto_close_parents);
+    }
+
     #[test]
     fn merge_deps_union() {
         let mut base = Store::default();
@@ -953,20 +2194,102 @@ mod tests {
         insert_item(&mut base, "lb-cccc", "c", Status::Open, 1);
 
         let mut ours = base.clone();
-        ours.deps.push(Dep {
+        ours.add_dep(Dep {
             from_id: "lb-aaaa".to_string(),
             to_id: "lb-bbbb".to_string(),
             dep_type: DepType::Blocks,
+            created_at: None,
         });
 
         let mut theirs = base.clone();
-        theirs.deps.push(Dep {
+        theirs.add_dep(Dep {
             from_id: "lb-bbbb".to_string(),
             to_id: "lb-cccc".to_string(),
             dep_type: DepType::Blocks,
+            created_at: None,
+        });
+
+        let (merged, _) = merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        assert_eq!(merged.deps().len(), 2);
+    }
+
+    #[test]
+    fn merge_resolves_concurrent_parents_and_cycles() {
+        let mut base = Store::default();
+        for id in ["lb-aaaa", "lb-bbbb", "lb-cccc"] {
+            insert_item(&mut base, id, id, Status::Open, 1);
+        }
+        let at = |secs: i64| Some(Utc::now() + chrono::Duration::seconds(secs));
+
+        let mut ours = base.clone();
+        ours.add_dep(Dep {
+            created_at: at(0),
+            ..Dep::new("lb-aaaa".into(), "lb-bbbb".into(), DepType::Blocks)
+        });
+        ours.add_dep(Dep {
+            created_at: at(0),
+            ..Dep::new("lb-cccc".into(), "lb-aaaa".into(), DepType::Parent)
+        });
+        let mut theirs = base.clone();
+        theirs.add_dep(Dep {
+            created_at: at(1),
+            ..Dep::new("lb-bbbb".into(), "lb-aaaa".into(), DepType::Blocks)
         });
+        theirs.add_dep(Dep {
+            created_at: at(1),
+            ..Dep::new("lb-cccc".into(), "lb-bbbb".into(), DepType::Parent)
+        });
+
+        let (merged, resolved) =
+            merge_stores(&base, &ours, &theirs, ClaimAuthority::Theirs).unwrap();
+        assert_eq!(resolved.len(), 2, "{resolved:?}");
+        assert_eq!(get_parent(&merged, "lb-cccc").as_deref(), Some("lb-bbbb"));
+        assert_eq!(get_blockers(&merged, "lb-bbbb"), vec!["lb-aaaa"]);
+        assert!(get_blockers(&merged, "lb-aaaa").is_empty());
+        assert!(fsck::check(&merged).is_empty());
+    }
 
-        let merged = merge_stores(&base, &ours, &theirs).unwrap();
-        assert_eq!(merged.deps.len(), 2);
+    /// Store of `n` items in epics of ten, each task blocked by the one
+    /// before it.
+    fn large_store(n: usize) -> Store {
+        let mut store = Store::default();
+        for i in 0..n {
+            insert_item(&mut store, &format!("lb-{i:06}"), "item", Status::Open, 2);
+            if i % 10 != 0 {
+                let epic = format!("lb-{:06}", i - i % 10);
+                store.add_dep(Dep::new(format!("lb-{i:06}"), epic, DepType::Parent));
+                let prev = format!("lb-{:06}", i - 1);
+                store.add_dep(Dep::new(prev, format!("lb-{i:06}"), DepType::Blocks));
+            }
+        }
+        store
+    }
+
+    /// Times the per-item lookups behind `lb ready` and `lb list --tree`,
+    /// including building the index, on stores ten times apart in size.
+    /// Run with `cargo test --release -- --ignored --nocapture bench_`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_dep_lookups_scale() {
+        let mut timings = Vec::new();
+        for n in [2_000, 20_000] {
+            let store = large_store(n);
+            let start = std::time::Instant::now();
+            let ready = ready_items(&store).len();
+            let roots = root_items(&store).len();
+            let children: usize = store
+                .items
+                .keys()
+                .map(|id| get_children(&store, id).len())
+                .sum();
+            let elapsed = start.elapsed();
+            println!(
+                "{n:>6} items, {:>6} deps: {elapsed:?} ({ready} ready, {roots} roots, {children} children)",
+                store.deps().len()
+            );
+            timings.push(elapsed);
+        }
+        // Linear would be 10x; scanning every dep per item was 100x
+        assert!(timings[1] < timings[0] * 30, "{timings:?}");
     }
 }
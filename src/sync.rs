@@ -0,0 +1,322 @@
+use crate::fsck::Problem;
+use crate::git;
+use crate::layout;
+use crate::model::Store;
+use crate::store;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Remote used when `litebrite.remote` isn't configured.
+const DEFAULT_REMOTE: &str = "origin";
+
+/// Local file (in the git directory, never pushed) holding queued claims.
+const PENDING_FILENAME: &str = "litebrite-pending.json";
+
+/// Push attempts before giving up: the first push plus retries.
+const MAX_ATTEMPTS: u32 = 6;
+const BASE_BACKOFF_MS: u64 = 50;
+const MAX_BACKOFF_MS: u64 = 2000;
+
+/// How the local branch relates to the remote one after [`integrate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Integration {
+    /// Local and remote point at the same commit.
+    InSync,
+    /// Local was behind and now matches the remote.
+    FastForwarded,
+    /// Local already contains the remote; only a push is needed.
+    Ahead,
+    /// Histories diverged and were joined with a merge commit, resolving
+    /// these dep conflicts along the way.
+    Merged(Vec<Problem>),
+}
+
+/// Remotes `lb sync` talks to, in order: every `litebrite.remote` entry in
+/// git config, or just `origin` when none are configured.
+pub fn remotes() -> Vec<String> {
+    let configured = git::config_get_all("litebrite.remote");
+    if configured.is_empty() {
+        vec![DEFAULT_REMOTE.to_string()]
+    } else {
+        configured
+    }
+}
+
+/// The remote whose claims are authoritative: `litebrite.primary`, or the
+/// first of [`remotes`]. Claim commands only ever talk to this remote.
+pub fn primary_remote() -> String {
+    git::config_get("litebrite.primary").unwrap_or_else(|| remotes().swap_remove(0))
+}
+
+/// Check remote state and sync if possible. Returns true if remote is available.
+/// - No remote configured: returns Ok(false) (local-only operation)
+/// - Remote exists, branch on remote: fetches + fast-forwards, returns Ok(true)
+/// - Remote exists, no branch on remote: returns Err with instructions
+pub fn from_remote(remote: &str) -> Result<bool, String> {
+    if !git::has_remote(remote) {
+        return Ok(false);
+    }
+    match git::fetch(remote) {
+        Ok(()) => {
+            git::fast_forward(remote)?;
+            Ok(true)
+        }
+        Err(_) => {
+            Err("litebrite branch not found on remote — run `lb sync` to push it first".to_string())
+        }
+    }
+}
+
+/// Fold the fetched remote branch into the local one: fast-forward when we
+/// are behind, schema-aware three-way merge when the histories diverged.
+///
+/// Claim conflicts go to the remote only if it is the primary one. A mirror
+/// is never fast-forwarded blindly: its claims are dropped in a merge commit
+/// unless they already match ours.
+pub fn integrate(remote: &str, message: &str) -> Result<Integration, String> {
+    let remote_ref = git::remote_ref(remote)?;
+    if git::local_ref()? == remote_ref {
+        return Ok(Integration::InSync);
+    }
+
+    let primary = remote == primary_remote();
+    if primary {
+        git::fast_forward(remote)?;
+    }
+    let local_ref = git::local_ref()?;
+    if local_ref == remote_ref {
+        return Ok(Integration::FastForwarded);
+    }
+
+    let base_commit = git::merge_base(remote)?;
+    if base_commit.as_deref() == Some(remote_ref.as_str()) {
+        return Ok(Integration::Ahead);
+    }
+    let claims = if primary {
+        store::ClaimAuthority::Theirs
+    } else {
+        store::ClaimAuthority::Ours
+    };
+    let (merged_tree, resolved) =
+        layout::merge(base_commit.as_deref(), &local_ref, &remote_ref, claims)?;
+    if base_commit.as_deref() == Some(local_ref.as_str())
+        && merged_tree == git::tree_of(&remote_ref)?
+    {
+        git::fast_forward(remote)?;
+        return Ok(Integration::FastForwarded);
+    }
+    let mut message = message.to_string();
+    if !resolved.is_empty() {
+        message.push_str("\n\nResolved dep conflicts:\n");
+        for problem in &resolved {
+            message.push_str(&format!("- {problem} — {}\n", problem.fix()));
+        }
+    }
+    git::create_merge_commit(&merged_tree, &local_ref, &remote_ref, &message)?;
+    Ok(Integration::Merged(resolved))
+}
+
+/// Push the local branch, retrying under contention.
+///
+/// Each rejected push is followed by a backoff, a fresh fetch, a call to
+/// `check` against the remote store — so callers can re-verify invariants
+/// such as claim atomicity against what others pushed — and an
+/// [`integrate`] before pushing again. Returns the number of attempts used.
+pub fn push_with_retry(
+    remote: &str,
+    message: &str,
+    check: impl Fn(&Store) -> Result<(), String>,
+) -> Result<u32, String> {
+    let mut last_err = String::new();
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            std::thread::sleep(backoff(attempt));
+            git::fetch(remote).map_err(|e| format!("fetch failed on retry: {e}"))?;
+            let remote_store = store_at(&git::remote_ref(remote)?)?;
+            check(&remote_store)?;
+            integrate(remote, &format!("Merge: {message}"))?;
+        }
+        match git::push(remote) {
+            Ok(()) => return Ok(attempt + 1),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(format!(
+        "push failed after {MAX_ATTEMPTS} attempts: {last_err}"
+    ))
+}
+
+/// A claim queued with `lb claim --offline`, confirmed by the next `lb sync`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingClaim {
+    pub id: String,
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    pub queued_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingOutcome {
+    /// The claim is now held on the remote.
+    Won,
+    /// Someone else got there first, or the item went away.
+    Lost(String),
+    /// Could not reach the remote; the claim stays queued.
+    StillPending(String),
+}
+
+fn pending_path() -> Result<std::path::PathBuf, String> {
+    Ok(git::common_dir()?.join(PENDING_FILENAME))
+}
+
+pub fn load_pending() -> Result<Vec<PendingClaim>, String> {
+    let path = pending_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = std::fs::read_to_string(&path).map_err(|e| format!("read pending claims: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("invalid pending claims file: {e}"))
+}
+
+fn save_pending(claims: &[PendingClaim]) -> Result<(), String> {
+    let path = pending_path()?;
+    if claims.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| format!("remove pending claims: {e}"))?;
+        }
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(claims).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json + "\n").map_err(|e| format!("write pending claims: {e}"))
+}
+
+/// Queue a claim for the next sync, replacing any earlier one for the item.
+pub fn queue_pending(claim: PendingClaim) -> Result<(), String> {
+    let mut claims = load_pending()?;
+    claims.retain(|c| c.id != claim.id);
+    claims.push(claim);
+    save_pending(&claims)
+}
+
+/// Queued claim for an item, for marking it tentative in output. The queue is
+/// read once per process; an unreadable queue is treated as empty.
+pub fn pending_claim(id: &str) -> Option<&'static PendingClaim> {
+    static PENDING: OnceLock<Vec<PendingClaim>> = OnceLock::new();
+    PENDING
+        .get_or_init(|| load_pending().unwrap_or_default())
+        .iter()
+        .find(|c| c.id == id)
+}
+
+/// Try to turn each queued claim into a real one on the primary remote. Call
+/// after the local branch has been integrated with and pushed to it, so the
+/// local store reflects what others have claimed. Each claim is committed and pushed on
+/// its own so one lost race doesn't sink the rest; claims that can't be
+/// pushed for network reasons stay queued.
+pub fn confirm_pending() -> Result<Vec<(PendingClaim, PendingOutcome)>, String> {
+    let remote = primary_remote();
+    let mut results = Vec::new();
+    let mut remaining = Vec::new();
+    for claim in load_pending()? {
+        if !remaining.is_empty() {
+            // Already offline — don't hammer the remote for the rest.
+            remaining.push(claim);
+            continue;
+        }
+        let outcome = confirm_one(&remote, &claim)?;
+        if matches!(outcome, PendingOutcome::StillPending(_)) {
+            remaining.push(claim.clone());
+        }
+        results.push((claim, outcome));
+    }
+    save_pending(&remaining)?;
+    Ok(results)
+}
+
+fn confirm_one(remote: &str, claim: &PendingClaim) -> Result<PendingOutcome, String> {
+    let mut local = layout::load(git::BRANCH)?;
+    let now = Utc::now();
+    match store::claim_status(&local, &claim.id, &claim.actor, now) {
+        Err(reason) => return Ok(PendingOutcome::Lost(reason)),
+        Ok(true) => return Ok(PendingOutcome::Won),
+        Ok(false) => {}
+    }
+    store::claim_item(&mut local, &claim.id, &claim.actor, claim.ttl, now)?;
+    let message = format!("{} claims {} (queued offline)", claim.actor, claim.id);
+    layout::save(&local, &message)?;
+
+    let vetoed = Cell::new(false);
+    let pushed = push_with_retry(remote, &message, |remote_store| {
+        let result = match store::claim_status(remote_store, &claim.id, &claim.actor, now) {
+            Err(reason) => Err(reason),
+            Ok(_) => Ok(()),
+        };
+        vetoed.set(result.is_err());
+        result
+    });
+    Ok(match pushed {
+        Ok(_) => PendingOutcome::Won,
+        Err(reason) if vetoed.get() => {
+            // Fold the winning claim back in so the local branch stops
+            // showing ours; the remote claim wins the merge.
+            integrate(remote, &format!("Merge: {message}"))?;
+            if let Err(e) = git::push(remote) {
+                eprintln!("warning: push to {remote} failed: {e}");
+            }
+            PendingOutcome::Lost(reason)
+        }
+        Err(reason) => PendingOutcome::StillPending(reason),
+    })
+}
+
+fn store_at(git_ref: &str) -> Result<Store, String> {
+    layout::load(git_ref)
+}
+
+/// Exponential delay before retry `attempt` (1-based), capped at
+/// `MAX_BACKOFF_MS`, without jitter.
+fn backoff_ms(attempt: u32) -> u64 {
+    let exp = attempt.saturating_sub(1).min(16);
+    (BASE_BACKOFF_MS << exp).min(MAX_BACKOFF_MS)
+}
+
+/// Backoff with up to 50% jitter so competing agents don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let ms = backoff_ms(attempt);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| u64::from(d.subsec_nanos()))
+        .unwrap_or(0);
+    Duration::from_millis(ms + nanos % (ms / 2 + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially() {
+        assert_eq!(backoff_ms(1), BASE_BACKOFF_MS);
+        assert_eq!(backoff_ms(2), BASE_BACKOFF_MS * 2);
+        assert_eq!(backoff_ms(3), BASE_BACKOFF_MS * 4);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff_ms(20), MAX_BACKOFF_MS);
+        assert_eq!(backoff_ms(u32::MAX), MAX_BACKOFF_MS);
+    }
+
+    #[test]
+    fn backoff_jitter_within_bounds() {
+        for attempt in 1..8 {
+            let d = backoff(attempt).as_millis() as u64;
+            let base = backoff_ms(attempt);
+            assert!(d >= base && d <= base + base / 2, "{attempt}: {d}");
+        }
+    }
+}
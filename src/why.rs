@@ -0,0 +1,285 @@
+//! Explaining why items are missing from `lb ready`, and what closing an
+//! item would let through.
+
+use crate::model::{Status, Store};
+use crate::store;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// An unclosed blocker, followed transitively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Blocker {
+    /// An open blocker and the unclosed blockers holding it up in turn.
+    Open { id: String, blockers: Vec<Blocker> },
+    /// Already explained further up (the chains meet or loop).
+    Seen(String),
+    /// A blocker ID with no item behind it, which counts as open.
+    Missing(String),
+}
+
+/// One reason an item isn't ready.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    Closed,
+    Claimed {
+        who: String,
+        expires_at: Option<DateTime<Utc>>,
+    },
+    Blocked(Blocker),
+}
+
+/// Every reason `id` is left out of `lb ready`; empty if it is ready.
+pub fn why_not_ready(store: &Store, id: &str, now: DateTime<Utc>) -> Vec<Reason> {
+    let Some(item) = store.items.get(id) else {
+        return Vec::new();
+    };
+    let mut reasons = Vec::new();
+    if item.status == Status::Closed {
+        reasons.push(Reason::Closed);
+    }
+    if let Some(who) = store::active_claimant(item, now) {
+        reasons.push(Reason::Claimed {
+            who: who.to_string(),
+            expires_at: store::claim_expires_at(item),
+        });
+    }
+    let mut seen = HashSet::from([id.to_string()]);
+    reasons.extend(
+        blockers(store, id, &mut seen)
+            .into_iter()
+            .map(Reason::Blocked),
+    );
+    reasons
+}
+
+/// Unclosed blockers of `id`, each with its own, depth first.
+fn blockers(store: &Store, id: &str, seen: &mut HashSet<String>) -> Vec<Blocker> {
+    let mut out = Vec::new();
+    for bid in store::get_blockers(store, id) {
+        match store.items.get(&bid) {
+            None => out.push(Blocker::Missing(bid)),
+            Some(b) if b.status == Status::Closed => {}
+            Some(_) if !seen.insert(bid.clone()) => out.push(Blocker::Seen(bid)),
+            Some(_) => {
+                let nested = blockers(store, &bid, seen);
+                out.push(Blocker::Open {
+                    id: bid,
+                    blockers: nested,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// The unclosed blockers at the far end of each of `id`'s blocking chains:
+/// the ones nothing else holds up, so working on them is what moves `id`.
+pub fn root_blockers(store: &Store, id: &str) -> Vec<String> {
+    fn walk(blocker: &Blocker, roots: &mut BTreeSet<String>) {
+        match blocker {
+            Blocker::Open { id, blockers } if blockers.is_empty() => {
+                roots.insert(id.clone());
+            }
+            Blocker::Open { blockers, .. } => {
+                for b in blockers {
+                    walk(b, roots);
+                }
+            }
+            Blocker::Missing(id) => {
+                roots.insert(id.clone());
+            }
+            Blocker::Seen(_) => {}
+        }
+    }
+    let mut seen = HashSet::from([id.to_string()]);
+    let direct = blockers(store, id, &mut seen);
+    let mut roots = BTreeSet::new();
+    for b in &direct {
+        walk(b, &mut roots);
+    }
+    if roots.is_empty() {
+        // Every chain loops back on itself; the direct blockers are all we have
+        for b in direct {
+            let (Blocker::Open { id, .. } | Blocker::Seen(id) | Blocker::Missing(id)) = b;
+            roots.insert(id);
+        }
+    }
+    roots.into_iter().collect()
+}
+
+/// Open items held up by at least one unclosed blocker, in ID order.
+pub fn blocked_items(store: &Store) -> Vec<&str> {
+    store
+        .items
+        .values()
+        .filter(|i| i.status == Status::Open && store::is_blocked(store, &i.id))
+        .map(|i| i.id.as_str())
+        .collect()
+}
+
+/// Open items that closing `id` would unblock, each with how many closes
+/// away it is: 1 for items only `id` holds up, 2 for items that also wait
+/// on those, and so on. Sorted by that distance, then ID. Items someone
+/// else holds an active claim on are left out, as `lb ready` leaves them
+/// out, though what waits on them still counts.
+pub fn unblocks(store: &Store, id: &str) -> Vec<(String, usize)> {
+    let mut freed: HashMap<String, usize> = HashMap::from([(id.to_string(), 0)]);
+    let mut frontier = vec![id.to_string()];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = Vec::new();
+        for done in &frontier {
+            for cand in store::get_blocking(store, done) {
+                if freed.contains_key(&cand)
+                    || store
+                        .items
+                        .get(&cand)
+                        .is_none_or(|i| i.status != Status::Open)
+                {
+                    continue;
+                }
+                let clear = store::get_blockers(store, &cand).iter().all(|b| {
+                    freed.contains_key(b)
+                        || store
+                            .items
+                            .get(b)
+                            .is_some_and(|i| i.status == Status::Closed)
+                });
+                if clear {
+                    freed.insert(cand.clone(), depth);
+                    next.push(cand);
+                }
+            }
+        }
+        frontier = next;
+    }
+    freed.remove(id);
+    let now = Utc::now();
+    let mut out: Vec<(String, usize)> = freed
+        .into_iter()
+        .filter(|(fid, _)| store::active_claimant(&store.items[fid], now).is_none())
+        .collect();
+    out.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Dep, DepType, Item};
+
+    fn store_with(ids: &[&str], deps: &[(&str, &str)]) -> Store {
+        let mut s = Store::default();
+        for id in ids {
+            s.items.insert(id.to_string(), Item::for_test(id));
+        }
+        for (from, to) in deps {
+            s.add_dep(Dep::new(from.to_string(), to.to_string(), DepType::Blocks));
+        }
+        s
+    }
+
+    fn open(id: &str, blockers: Vec<Blocker>) -> Blocker {
+        Blocker::Open {
+            id: id.to_string(),
+            blockers,
+        }
+    }
+
+    #[test]
+    fn walks_the_whole_chain() {
+        // lb-a blocks lb-b blocks lb-x; lb-c (closed) and lb-gone block lb-x
+        let mut s = store_with(
+            &["lb-a", "lb-b", "lb-c", "lb-x"],
+            &[
+                ("lb-a", "lb-b"),
+                ("lb-b", "lb-x"),
+                ("lb-c", "lb-x"),
+                ("lb-gone", "lb-x"),
+            ],
+        );
+        s.items.get_mut("lb-c").unwrap().status = Status::Closed;
+        s.items.get_mut("lb-x").unwrap().claimed_by = Some("alice".to_string());
+
+        let reasons = why_not_ready(&s, "lb-x", Utc::now());
+        assert_eq!(
+            reasons,
+            vec![
+                Reason::Claimed {
+                    who: "alice".to_string(),
+                    expires_at: None
+                },
+                Reason::Blocked(open("lb-b", vec![open("lb-a", vec![])])),
+                Reason::Blocked(Blocker::Missing("lb-gone".to_string())),
+            ]
+        );
+        assert_eq!(root_blockers(&s, "lb-x"), ["lb-a", "lb-gone"]);
+        assert!(why_not_ready(&s, "lb-a", Utc::now()).is_empty());
+        assert_eq!(blocked_items(&s), ["lb-b", "lb-x"]);
+    }
+
+    #[test]
+    fn shared_and_looping_blockers_are_listed_once() {
+        // lb-a blocks both lb-b and lb-c, which both block lb-x
+        let s = store_with(
+            &["lb-a", "lb-b", "lb-c", "lb-x"],
+            &[
+                ("lb-a", "lb-b"),
+                ("lb-a", "lb-c"),
+                ("lb-b", "lb-x"),
+                ("lb-c", "lb-x"),
+            ],
+        );
+        let reasons = why_not_ready(&s, "lb-x", Utc::now());
+        assert_eq!(
+            reasons[1],
+            Reason::Blocked(open("lb-c", vec![Blocker::Seen("lb-a".to_string())]))
+        );
+        assert_eq!(root_blockers(&s, "lb-x"), ["lb-a"]);
+
+        let looped = store_with(&["lb-a", "lb-b"], &[("lb-a", "lb-b"), ("lb-b", "lb-a")]);
+        assert_eq!(
+            why_not_ready(&looped, "lb-a", Utc::now()),
+            vec![Reason::Blocked(open(
+                "lb-b",
+                vec![Blocker::Seen("lb-a".to_string())]
+            ))]
+        );
+        assert_eq!(root_blockers(&looped, "lb-a"), ["lb-b"]);
+    }
+
+    #[test]
+    fn unblocks_follows_items_only_waiting_on_the_chain() {
+        // lb-a blocks lb-b and lb-c; lb-b blocks lb-d; lb-c and lb-x block lb-e
+        let mut s = store_with(
+            &["lb-a", "lb-b", "lb-c", "lb-d", "lb-e", "lb-x"],
+            &[
+                ("lb-a", "lb-b"),
+                ("lb-a", "lb-c"),
+                ("lb-b", "lb-d"),
+                ("lb-c", "lb-e"),
+                ("lb-x", "lb-e"),
+            ],
+        );
+        assert_eq!(
+            unblocks(&s, "lb-a"),
+            vec![
+                ("lb-b".to_string(), 1),
+                ("lb-c".to_string(), 1),
+                ("lb-d".to_string(), 2)
+            ]
+        );
+        assert!(unblocks(&s, "lb-d").is_empty());
+
+        // Once lb-x is done, lb-e only waits on lb-a's chain
+        s.items.get_mut("lb-x").unwrap().status = Status::Closed;
+        assert_eq!(unblocks(&s, "lb-a").len(), 4);
+        assert_eq!(unblocks(&s, "lb-c"), vec![("lb-e".to_string(), 1)]);
+
+        // A claimed item won't show up in lb ready, but what it holds up will
+        s.items.get_mut("lb-b").unwrap().claimed_by = Some("bob".to_string());
+        let ids: Vec<String> = unblocks(&s, "lb-a").into_iter().map(|(i, _)| i).collect();
+        assert_eq!(ids, ["lb-c", "lb-d", "lb-e"]);
+    }
+}
@@ -1,3 +1,6 @@
+// Older tests trim before split_whitespace; harmless, so allowed.
+#![allow(clippy::trim_split_whitespace)]
+
 use std::process::Command;
 use tempfile::TempDir;
 
@@ -80,3 +83,1187 @@ fn close_with_open_children_fails() {
     let (stdout, _, ok) = lb(&dir, &["close", &epic_id]);
     assert!(ok, "close epic should succeed now: {stdout}");
 }
+
+#[test]
+fn close_cascade_closes_descendants_in_one_commit() {
+    let dir = setup_git_dir();
+
+    let (stdout, _, _) = lb(&dir, &["create", "my-epic", "-t", "epic"]);
+    let epic_id = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "child", "--parent", &epic_id]);
+    let child_id = stdout.split_whitespace().last().unwrap().to_string();
+
+    let count_commits = || {
+        let out = Command::new("git")
+            .args(["rev-list", "--count", "litebrite"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    };
+    let before = count_commits();
+
+    let (stdout, stderr, ok) = lb(&dir, &["close", "--cascade", &epic_id]);
+    assert!(ok, "close --cascade failed: {stderr}");
+    assert!(stdout.contains(&format!("closed {child_id}")), "{stdout}");
+    assert!(stdout.contains(&format!("closed {epic_id}")), "{stdout}");
+
+    let after = count_commits();
+    assert_eq!(
+        after.parse::<u32>().unwrap(),
+        before.parse::<u32>().unwrap() + 1
+    );
+}
+
+#[test]
+fn auto_close_parents_policy() {
+    let dir = setup_git_dir();
+
+    let (stdout, _, ok) = lb(&dir, &["config", "auto_close_parents"]);
+    assert!(ok);
+    assert_eq!(stdout.trim(), "false");
+
+    let (_, stderr, ok) = lb(&dir, &["config", "auto_close_parents", "true"]);
+    assert!(ok, "config set failed: {stderr}");
+
+    let (stdout, _, _) = lb(&dir, &["create", "my-epic", "-t", "epic"]);
+    let epic_id = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "child", "--parent", &epic_id]);
+    let child_id = stdout.split_whitespace().last().unwrap().to_string();
+
+    let (stdout, _, ok) = lb(&dir, &["close", &child_id]);
+    assert!(ok);
+    assert!(stdout.contains(&format!("closed {epic_id}")), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["show", &epic_id]);
+    assert!(stdout.contains("Status: closed"), "{stdout}");
+}
+
+#[test]
+fn update_status_closed_closes_like_close() {
+    let dir = setup_git_dir();
+    lb(&dir, &["config", "auto_close_parents", "true"]);
+    let (stdout, _, _) = lb(&dir, &["create", "my-epic", "-t", "epic"]);
+    let epic_id = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "child", "--parent", &epic_id]);
+    let child_id = stdout.split_whitespace().last().unwrap().to_string();
+
+    let (_, stderr, ok) = lb(&dir, &["update", &epic_id, "--status", "closed"]);
+    assert!(!ok);
+    assert!(stderr.contains("has open children"), "{stderr}");
+
+    let (stdout, stderr, ok) = lb(&dir, &["update", &child_id, "--status", "closed"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains(&format!("closed {epic_id}")), "{stdout}");
+    let (stdout, _, _) = lb(&dir, &["show", &epic_id]);
+    assert!(stdout.contains("Status: closed"), "{stdout}");
+}
+
+#[test]
+fn claim_with_ttl_and_renew() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "leased"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+
+    let (stdout, stderr, ok) = lb(&dir, &["claim", &id, "--ttl", "4h"]);
+    assert!(ok, "claim --ttl failed: {stderr}");
+    assert!(stdout.contains("expires in 4h"), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["show", &id]);
+    assert!(stdout.contains("expires in"), "{stdout}");
+
+    let (stdout, stderr, ok) = lb(&dir, &["claim", &id, "--renew", "--ttl", "1d"]);
+    assert!(ok, "claim --renew failed: {stderr}");
+    assert!(stdout.contains("renewed claim"), "{stdout}");
+
+    let (stdout, _, ok) = lb(&dir, &["claims"]);
+    assert!(ok);
+    assert!(stdout.contains(&id), "{stdout}");
+
+    let (stdout, _, ok) = lb(&dir, &["claims", "--stale"]);
+    assert!(ok);
+    assert!(stdout.contains("no stale claims"), "{stdout}");
+
+    let (_, stderr, ok) = lb(&dir, &["claim", &id, "--ttl", "soon"]);
+    assert!(!ok);
+    assert!(stderr.contains("invalid duration"), "{stderr}");
+}
+
+#[test]
+fn claim_identity_from_flag_and_env() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "first"]);
+    let first = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "second"]);
+    let second = stdout.split_whitespace().last().unwrap().to_string();
+
+    let (stdout, stderr, ok) = lb(&dir, &["claim", &first, "--as", "agent-1"]);
+    assert!(ok, "claim --as failed: {stderr}");
+    assert!(stdout.contains("agent-1"), "{stdout}");
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lb"))
+        .args(["claim", &second])
+        .env("LB_ACTOR", "agent-2")
+        .env("LB_SESSION", "s42")
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("agent-2/s42"));
+
+    let (stdout, _, _) = lb(&dir, &["list", "--claimed-by", "agent-1"]);
+    assert!(stdout.contains("first"), "{stdout}");
+    assert!(!stdout.contains("second"), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["ready", "--mine", "--as", "agent-1"]);
+    assert!(stdout.contains("first"), "{stdout}");
+    assert!(!stdout.contains("second"), "{stdout}");
+
+    // A different identity cannot see agent-1's claim as its own
+    let (stdout, _, _) = lb(&dir, &["ready", "--mine", "--as", "someone-else"]);
+    assert!(stdout.contains("no ready claimed items"), "{stdout}");
+}
+
+#[test]
+fn steal_handoff_and_forced_unclaim_are_audited() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "contested"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+
+    let (_, _, ok) = lb(&dir, &["claim", &id, "--as", "alice"]);
+    assert!(ok);
+
+    // Plain claim and plain unclaim refuse to touch alice's claim
+    let (_, stderr, ok) = lb(&dir, &["claim", &id, "--as", "bob"]);
+    assert!(!ok);
+    assert!(stderr.contains("already claimed by alice"), "{stderr}");
+    let (_, stderr, ok) = lb(&dir, &["unclaim", &id, "--as", "bob"]);
+    assert!(!ok);
+    assert!(stderr.contains("--force"), "{stderr}");
+
+    let (stdout, stderr, ok) = lb(
+        &dir,
+        &[
+            "claim",
+            "--steal",
+            &id,
+            "--as",
+            "bob",
+            "--reason",
+            "on holiday",
+        ],
+    );
+    assert!(ok, "steal failed: {stderr}");
+    assert!(stdout.contains("taken over from alice"), "{stdout}");
+
+    let (_, stderr, ok) = lb(&dir, &["handoff", &id, "carol", "--as", "bob"]);
+    assert!(ok, "handoff failed: {stderr}");
+
+    let (_, stderr, ok) = lb(&dir, &["unclaim", &id, "--as", "bob", "--force"]);
+    assert!(ok, "forced unclaim failed: {stderr}");
+
+    let (stdout, _, _) = lb(&dir, &["show", &id]);
+    assert!(stdout.contains("History:"), "{stdout}");
+    assert!(
+        stdout.contains("steal by bob: alice -> bob (on holiday)"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("handoff by bob: bob -> carol"), "{stdout}");
+    assert!(
+        stdout.contains("force-unclaim by bob: released carol"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("Claimed by:"), "{stdout}");
+}
+
+/// Two clones sharing a bare remote, both with litebrite initialized.
+fn setup_remote_pair() -> (TempDir, TempDir, TempDir) {
+    let bare = TempDir::new().unwrap();
+    git(&bare, &["init", "--bare"]);
+    let clone = || {
+        let dir = TempDir::new().unwrap();
+        git(&dir, &["init"]);
+        git(&dir, &["config", "user.name", "Test"]);
+        git(&dir, &["config", "user.email", "test@test.com"]);
+        git(
+            &dir,
+            &["remote", "add", "origin", bare.path().to_str().unwrap()],
+        );
+        let (_, stderr, ok) = lb(&dir, &["init"]);
+        assert!(ok, "init failed: {stderr}");
+        dir
+    };
+    let a = clone();
+    let b = clone();
+    (a, b, bare)
+}
+
+fn git(dir: &TempDir, args: &[&str]) -> String {
+    let out = Command::new("git")
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8_lossy(&out.stdout).trim().to_string()
+}
+
+#[test]
+fn claim_merges_and_retries_after_rejected_push() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "shared"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+    lb(&b, &["sync"]);
+
+    // b pushes an unrelated change; a has an unpushed local change, so
+    // a's claim push is rejected and must fetch, merge and retry.
+    lb(&b, &["create", "from b"]);
+    lb(&b, &["sync"]);
+    lb(&a, &["create", "from a"]);
+
+    let (stdout, stderr, ok) = lb(&a, &["claim", &id, "--as", "alice"]);
+    assert!(ok, "claim should succeed after merge: {stderr}");
+    assert!(stdout.contains("claimed"), "{stdout}");
+
+    lb(&b, &["sync"]);
+    let (stdout, _, _) = lb(&b, &["list"]);
+    assert!(stdout.contains("from a"), "{stdout}");
+    assert!(stdout.contains("open (claimed)"), "{stdout}");
+}
+
+#[test]
+fn releasing_stale_claims_pushes() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "abandoned"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+    let (_, stderr, ok) = lb(&a, &["claim", &id, "--as", "alice", "--ttl", "1s"]);
+    assert!(ok, "{stderr}");
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    let (stdout, stderr, ok) = lb(&b, &["claims", "--stale", "--release"]);
+    assert!(ok, "{stderr}");
+    assert!(
+        stdout.contains(&format!("released {id} (was alice)")),
+        "{stdout}"
+    );
+
+    lb(&a, &["sync"]);
+    let (stdout, _, _) = lb(&a, &["claims"]);
+    assert!(stdout.contains("no claims"), "{stdout}");
+}
+
+#[test]
+fn claim_loses_race_to_remote_claim() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "contested"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+    lb(&b, &["sync"]);
+
+    // a diverges locally so its pre-claim fast-forward can't see b's claim
+    lb(&a, &["create", "local only"]);
+    let (_, stderr, ok) = lb(&b, &["claim", &id, "--as", "bob"]);
+    assert!(ok, "bob's claim failed: {stderr}");
+
+    let (_, stderr, ok) = lb(&a, &["claim", &id, "--as", "alice"]);
+    assert!(!ok, "alice should lose the race");
+    assert!(stderr.contains("already claimed by bob"), "{stderr}");
+}
+
+#[test]
+fn offline_claim_is_confirmed_on_sync() {
+    let (a, _b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "on the train"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+
+    let (stdout, stderr, ok) = lb(&a, &["claim", &id, "--offline", "--as", "alice"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("queued offline claim"), "{stdout}");
+    let (stdout, _, _) = lb(&a, &["list"]);
+    assert!(stdout.contains("open (pending)"), "{stdout}");
+    let (stdout, _, _) = lb(&a, &["show", &id]);
+    assert!(stdout.contains("tentative"), "{stdout}");
+
+    let (stdout, stderr, ok) = lb(&a, &["sync"]);
+    assert!(ok, "{stderr}");
+    assert!(
+        stdout.contains(&format!("confirmed offline claim on {id} (alice)")),
+        "{stdout}"
+    );
+    let (stdout, _, _) = lb(&a, &["list"]);
+    assert!(stdout.contains("open (claimed)"), "{stdout}");
+    let remote = git(&a, &["show", "origin/litebrite:store.json"]);
+    assert!(remote.contains("\"claimed_by\": \"alice\""), "{remote}");
+}
+
+#[test]
+fn offline_claim_lost_to_earlier_remote_claim() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "contested"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+    lb(&b, &["sync"]);
+
+    let (_, stderr, ok) = lb(&a, &["claim", &id, "--offline", "--as", "alice"]);
+    assert!(ok, "{stderr}");
+    let (_, stderr, ok) = lb(&b, &["claim", &id, "--as", "bob"]);
+    assert!(ok, "{stderr}");
+
+    let (stdout, stderr, ok) = lb(&a, &["sync"]);
+    assert!(ok, "{stderr}");
+    assert!(
+        stdout.contains(&format!("lost offline claim on {id}")),
+        "{stdout}"
+    );
+    assert!(stdout.contains("bob"), "{stdout}");
+    let (stdout, _, _) = lb(&a, &["show", &id]);
+    assert!(stdout.contains("Claimed by: bob"), "{stdout}");
+
+    // The queue is drained: a second sync reports nothing
+    let (stdout, _, _) = lb(&a, &["sync"]);
+    assert!(!stdout.contains("offline claim"), "{stdout}");
+}
+
+#[test]
+fn sync_with_multiple_remotes() {
+    let (a, b, _bare) = setup_remote_pair();
+    let upstream = TempDir::new().unwrap();
+    git(&upstream, &["init", "--bare"]);
+    let upstream_path = upstream.path().to_str().unwrap();
+    git(&a, &["remote", "add", "upstream", upstream_path]);
+    git(&a, &["config", "--add", "litebrite.remote", "origin"]);
+    git(&a, &["config", "--add", "litebrite.remote", "upstream"]);
+
+    let (stdout, _, _) = lb(&a, &["create", "public task"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, stderr, ok) = lb(&a, &["sync"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("origin: synced with remote"), "{stdout}");
+    assert!(
+        stdout.contains("upstream: pushed litebrite branch to remote"),
+        "{stdout}"
+    );
+    assert_eq!(
+        git(&a, &["rev-parse", "refs/remotes/upstream/litebrite"]),
+        git(&a, &["rev-parse", "refs/remotes/origin/litebrite"])
+    );
+
+    let (stdout, stderr, ok) = lb(&a, &["sync", "--remote", "upstream"]);
+    assert!(ok, "{stderr}");
+    assert_eq!(stdout.trim(), "already in sync");
+    let (_, stderr, ok) = lb(&a, &["sync", "--remote", "nope"]);
+    assert!(!ok);
+    assert!(stderr.contains("remote nope does not exist"), "{stderr}");
+
+    // A claim made directly on the mirror isn't authoritative...
+    let c = TempDir::new().unwrap();
+    git(&c, &["init"]);
+    git(&c, &["config", "user.name", "Test"]);
+    git(&c, &["config", "user.email", "test@test.com"]);
+    git(&c, &["remote", "add", "origin", upstream_path]);
+    lb(&c, &["init"]);
+    let (_, stderr, ok) = lb(&c, &["claim", &id, "--as", "carol"]);
+    assert!(ok, "{stderr}");
+    let (stdout, stderr, ok) = lb(&a, &["sync"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("upstream: synced with remote"), "{stdout}");
+    let (stdout, _, _) = lb(&a, &["show", &id]);
+    assert!(!stdout.contains("carol"), "{stdout}");
+
+    // ...while one on the primary is
+    lb(&b, &["sync"]);
+    let (_, stderr, ok) = lb(&b, &["claim", &id, "--as", "bob"]);
+    assert!(ok, "{stderr}");
+    lb(&a, &["sync"]);
+    let (stdout, _, _) = lb(&a, &["show", &id]);
+    assert!(stdout.contains("Claimed by: bob"), "{stdout}");
+    let mirror = git(&a, &["show", "refs/remotes/upstream/litebrite:store.json"]);
+    assert!(mirror.contains("\"claimed_by\": \"bob\""), "{mirror}");
+}
+
+#[test]
+fn status_reports_ahead_behind_and_local_changes() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "shared"]);
+    let shared = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+    let (stdout, _, _) = lb(&a, &["status"]);
+    assert!(stdout.contains("origin: up to date"), "{stdout}");
+    assert!(stdout.contains("no unpushed item changes"), "{stdout}");
+
+    let (stdout, _, _) = lb(&a, &["create", "local"]);
+    let local = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["update", &shared, "--priority", "0"]);
+    lb(&b, &["sync"]);
+    lb(&b, &["create", "remote"]);
+    lb(&b, &["sync"]);
+
+    // Without --fetch, a knows nothing of b's push
+    let (stdout, _, _) = lb(&a, &["status"]);
+    assert!(stdout.contains("origin: 2 ahead, 0 behind"), "{stdout}");
+    assert!(stdout.contains(&format!("added    {local}")), "{stdout}");
+    assert!(stdout.contains(&format!("modified {shared}")), "{stdout}");
+
+    let (stdout, _, _) = lb(&a, &["status", "--fetch"]);
+    assert!(stdout.contains("origin: 2 ahead, 1 behind"), "{stdout}");
+}
+
+#[test]
+fn diff_between_refs_and_as_textconv() {
+    let (a, _b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "first"]);
+    let first = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+    let (stdout, _, _) = lb(&a, &["diff"]);
+    assert_eq!(stdout.trim(), "no changes");
+
+    let (stdout, _, _) = lb(&a, &["create", "second"]);
+    let second = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["update", &first, "--priority", "0"]);
+    lb(&a, &["dep", "add", &first, "--blocks", &second]);
+
+    let (stdout, stderr, ok) = lb(&a, &["diff", "origin/litebrite"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains(&format!("+ {second} second")), "{stdout}");
+    assert!(stdout.contains(&format!("~ {first} first")), "{stdout}");
+    assert!(stdout.contains("    priority: 2 -> 0"), "{stdout}");
+    assert!(
+        stdout.contains(&format!("+ dep {first} blocks {second}")),
+        "{stdout}"
+    );
+
+    // Default direction is local -> remote, so the new item shows as removed
+    let (stdout, _, _) = lb(&a, &["diff", "--json"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let change = json["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["id"] == second.as_str())
+        .unwrap();
+    assert_eq!(change["change"], "removed");
+    assert_eq!(json["deps_removed"].as_array().unwrap().len(), 1);
+
+    let (_, stderr, ok) = lb(&a, &["diff", "no-such-ref"]);
+    assert!(!ok);
+    assert!(
+        stderr.contains("cannot read store at no-such-ref"),
+        "{stderr}"
+    );
+
+    // As a textconv, git log -p shows one field per line
+    git(
+        &a,
+        &[
+            "config",
+            "diff.litebrite.textconv",
+            &format!("{} diff --textconv", env!("CARGO_BIN_EXE_lb")),
+        ],
+    );
+    std::fs::write(
+        a.path().join(".git/info/attributes"),
+        "store.json diff=litebrite\n",
+    )
+    .unwrap();
+    let log = git(&a, &["log", "-p", "litebrite"]);
+    assert!(log.contains(&format!("+item {second}")), "{log}");
+    assert!(log.contains("+  title: second"), "{log}");
+}
+
+#[test]
+fn plain_git_merge_uses_merge_driver() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, ok) = lb(&a, &["setup", "git"]);
+    assert!(ok);
+    assert!(stdout.contains("added .gitattributes"), "{stdout}");
+    let (stdout, _, _) = lb(&a, &["setup", "git"]);
+    assert!(stdout.contains("already set up"), "{stdout}");
+    assert_eq!(
+        git(&a, &["config", "merge.litebrite.driver"]),
+        "lb merge-driver %O %A %B"
+    );
+    let (stdout, _, _) = lb(&a, &["create", "shared"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["sync"]);
+    lb(&b, &["sync"]);
+
+    // Both sides edit the same item (and so the same updated_at line)
+    lb(&b, &["update", &id, "--title", "retitled by b"]);
+    lb(&b, &["sync"]);
+    lb(&a, &["update", &id, "--priority", "0"]);
+    // The attributes file survives ordinary lb writes
+    assert!(git(&a, &["show", "litebrite:.gitattributes"]).contains("merge=litebrite"));
+
+    let wt = TempDir::new().unwrap();
+    let wt_path = wt.path().join("wt");
+    git(
+        &a,
+        &["worktree", "add", wt_path.to_str().unwrap(), "litebrite"],
+    );
+    let bin_dir = std::path::Path::new(env!("CARGO_BIN_EXE_lb"))
+        .parent()
+        .unwrap();
+    let path = format!(
+        "{}:{}",
+        bin_dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let run = |args: &[&str]| {
+        let out = Command::new("git")
+            .args(args)
+            .current_dir(&wt_path)
+            .env("PATH", &path)
+            .output()
+            .unwrap();
+        assert!(
+            out.status.success(),
+            "git {args:?} failed: {}{}",
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        );
+    };
+    run(&["fetch", "origin"]);
+    run(&["merge", "--no-edit", "origin/litebrite"]);
+
+    let merged = std::fs::read_to_string(wt_path.join("store.json")).unwrap();
+    assert!(merged.contains("retitled by b"), "{merged}");
+    assert!(merged.contains("\"priority\": 0"), "{merged}");
+    assert!(!merged.contains("<<<<<<<"), "{merged}");
+}
+
+#[test]
+fn per_item_layout_migrates_and_merges() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "first"]);
+    let first = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&a, &["create", "second"]);
+    let second = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["dep", "add", &first, "--blocks", &second]);
+
+    // Switching the setting rewrites the branch in the new layout
+    let (_, stderr, ok) = lb(&a, &["config", "layout", "per-item"]);
+    assert!(ok, "{stderr}");
+    let files = git(&a, &["ls-tree", "-r", "--name-only", "litebrite"]);
+    assert!(!files.contains("store.json"), "{files}");
+    assert!(files.contains(&format!("items/{first}.json")), "{files}");
+    assert!(
+        files.contains("deps.json") && files.contains("meta.json"),
+        "{files}"
+    );
+    let (stdout, _, _) = lb(&a, &["ready"]);
+    assert!(
+        stdout.contains(&first) && !stdout.contains(&second),
+        "{stdout}"
+    );
+
+    // A change to one item only touches that item's file
+    lb(&a, &["update", &first, "--priority", "0"]);
+    let changed = git(
+        &a,
+        &["diff-tree", "-r", "--name-only", "litebrite~1", "litebrite"],
+    );
+    assert_eq!(changed, format!("items/{first}.json"));
+    let (stdout, _, _) = lb(&a, &["diff", "litebrite~2"]);
+    assert!(stdout.contains("priority: 2 -> 0"), "{stdout}");
+
+    lb(&a, &["sync"]);
+    lb(&b, &["sync"]);
+    let (stdout, _, _) = lb(&b, &["show", &first]);
+    assert!(stdout.contains("Priority: P0"), "{stdout}");
+
+    // Concurrent edits on both clones merge item by item
+    lb(&a, &["update", &first, "--title", "first, renamed"]);
+    lb(&b, &["update", &first, "--priority", "1"]);
+    lb(&b, &["create", "third"]);
+    lb(&b, &["sync"]);
+    let (_, stderr, ok) = lb(&a, &["sync"]);
+    assert!(ok, "{stderr}");
+    let (stdout, _, _) = lb(&a, &["show", &first]);
+    assert!(stdout.contains("first, renamed"), "{stdout}");
+    assert!(stdout.contains("Priority: P1"), "{stdout}");
+    let (stdout, _, _) = lb(&a, &["list"]);
+    assert!(stdout.contains("third"), "{stdout}");
+    let files = git(&a, &["ls-tree", "-r", "--name-only", "litebrite"]);
+    assert!(!files.contains("store.json"), "{files}");
+}
+
+/// Replace the store on the litebrite branch with `store` via git plumbing.
+fn commit_store_json(dir: &TempDir, store: &serde_json::Value) {
+    let path = dir.path().join(".git").join("store-edit.json");
+    std::fs::write(&path, serde_json::to_string_pretty(store).unwrap()).unwrap();
+    let blob = git(dir, &["hash-object", "-w", path.to_str().unwrap()]);
+    let tree_spec = format!("100644 blob {blob}\tstore.json\n");
+    let out = Command::new("git")
+        .args(["mktree"])
+        .current_dir(dir.path())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(tree_spec.as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    let tree = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    let commit = git(
+        dir,
+        &["commit-tree", &tree, "-p", "litebrite", "-m", "edit store"],
+    );
+    git(dir, &["update-ref", "refs/heads/litebrite", &commit]);
+}
+
+fn read_store_json(dir: &TempDir) -> serde_json::Value {
+    serde_json::from_str(&git(dir, &["show", "litebrite:store.json"])).unwrap()
+}
+
+#[test]
+fn migrate_old_store_and_keep_unknown_fields() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "legacy"]);
+    let id = stdout.split_whitespace().last().unwrap().to_string();
+
+    // An unversioned store with a legacy status and fields from the future
+    let mut store = read_store_json(&dir);
+    let obj = store.as_object_mut().unwrap();
+    obj.remove("schema_version");
+    obj.insert("future_table".into(), serde_json::json!({ "k": 1 }));
+    let item = &mut store["items"][&id];
+    item["status"] = "in_progress".into();
+    item["future_field"] = "keep me".into();
+    commit_store_json(&dir, &store);
+
+    let (stdout, stderr, ok) = lb(&dir, &["migrate", "--dry-run"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("legacy statuses"), "{stdout}");
+    assert!(read_store_json(&dir).get("schema_version").is_none());
+
+    let (stdout, stderr, ok) = lb(&dir, &["migrate"]);
+    assert!(ok, "{stderr}");
+    assert!(
+        stdout.contains("migrated store from schema v1 to v2"),
+        "{stdout}"
+    );
+    let store = read_store_json(&dir);
+    assert_eq!(store["schema_version"], 2);
+    assert_eq!(store["items"][&id]["status"], "open");
+    let (stdout, _, _) = lb(&dir, &["migrate"]);
+    assert!(stdout.contains("already at schema v2"), "{stdout}");
+
+    // Unknown fields survive an ordinary write
+    let (_, stderr, ok) = lb(&dir, &["update", &id, "--priority", "0"]);
+    assert!(ok, "{stderr}");
+    let mut store = read_store_json(&dir);
+    assert_eq!(store["items"][&id]["future_field"], "keep me");
+    assert_eq!(store["future_table"]["k"], 1);
+    assert_eq!(store["items"][&id]["priority"], 0);
+
+    // A store from a newer lb is readable but not writable
+    store["schema_version"] = 99.into();
+    commit_store_json(&dir, &store);
+    let (stdout, _, ok) = lb(&dir, &["list"]);
+    assert!(ok && stdout.contains("legacy"), "{stdout}");
+    let (_, stderr, ok) = lb(&dir, &["update", &id, "--priority", "1"]);
+    assert!(!ok);
+    assert!(stderr.contains("upgrade lb"), "{stderr}");
+    let (_, stderr, ok) = lb(&dir, &["migrate"]);
+    assert!(!ok && stderr.contains("upgrade lb"), "{stderr}");
+}
+
+#[test]
+fn fsck_reports_and_repairs_in_one_commit() {
+    let dir = setup_git_dir();
+    let mut ids = Vec::new();
+    for title in ["a", "b"] {
+        let (stdout, _, _) = lb(&dir, &["create", title]);
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    let (_, _, ok) = lb(&dir, &["fsck"]);
+    assert!(ok);
+
+    let mut store = read_store_json(&dir);
+    store["deps"] = serde_json::json!([
+        { "from_id": ids[0], "to_id": ids[1], "dep_type": "blocks" },
+        { "from_id": ids[1], "to_id": ids[0], "dep_type": "blocks" },
+        { "from_id": ids[0], "to_id": "lb-gone", "dep_type": "parent" },
+    ]);
+    commit_store_json(&dir, &store);
+
+    let (stdout, stderr, ok) = lb(&dir, &["fsck"]);
+    assert!(!ok);
+    assert!(stderr.contains("2 problem(s) found"), "{stderr}");
+    assert!(stdout.contains("missing item lb-gone"), "{stdout}");
+    assert!(stdout.contains("blocking cycle"), "{stdout}");
+
+    let before = git(&dir, &["rev-list", "--count", "litebrite"]);
+    let (stdout, stderr, ok) = lb(&dir, &["fsck", "--repair"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("repaired 2 problem(s)"), "{stdout}");
+    let after = git(&dir, &["rev-list", "--count", "litebrite"]);
+    assert_eq!(
+        after.parse::<u32>().unwrap(),
+        before.parse::<u32>().unwrap() + 1
+    );
+    assert_eq!(read_store_json(&dir)["deps"].as_array().unwrap().len(), 1);
+    let (stdout, _, ok) = lb(&dir, &["fsck"]);
+    assert!(ok && stdout.contains("no problems found"), "{stdout}");
+}
+
+#[test]
+fn sync_resolves_concurrent_parents_and_cycles() {
+    let (a, b, _bare) = setup_remote_pair();
+    let mut ids = Vec::new();
+    for title in ["x", "y", "child"] {
+        let (stdout, _, _) = lb(&a, &["create", title]);
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    lb(&a, &["sync"]);
+    lb(&b, &["sync"]);
+
+    lb(&a, &["dep", "add", &ids[0], "--blocks", &ids[1]]);
+    lb(&a, &["update", &ids[2], "--parent", &ids[0]]);
+    lb(&a, &["sync"]);
+    lb(&b, &["dep", "add", &ids[1], "--blocks", &ids[0]]);
+    lb(&b, &["update", &ids[2], "--parent", &ids[1]]);
+    let (stdout, stderr, ok) = lb(&b, &["sync"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("resolved blocking cycle"), "{stdout}");
+    assert!(
+        stdout.contains(&format!("kept parent {}", ids[1])),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!("removed dep {} blocks {}", ids[1], ids[0])),
+        "{stdout}"
+    );
+    let message = git(&b, &["log", "-1", "--format=%B", "litebrite"]);
+    assert!(message.contains("Resolved dep conflicts"), "{message}");
+    let (stdout, _, ok) = lb(&b, &["fsck"]);
+    assert!(ok && stdout.contains("no problems found"), "{stdout}");
+}
+
+#[test]
+fn graph_renders_dot_and_mermaid() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "epic", "-t", "epic"]);
+    let epic = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "first", "--parent", &epic]);
+    let first = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "second", "--parent", &epic]);
+    let second = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&dir, &["create", "unrelated"]);
+    lb(&dir, &["dep", "add", &first, "--blocks", &second]);
+
+    let (stdout, stderr, ok) = lb(&dir, &["graph", &epic]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.starts_with("digraph litebrite {"), "{stdout}");
+    assert!(
+        stdout.contains(&format!("\"{first}\" -> \"{second}\" [label=\"blocks\"]")),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("unrelated"), "{stdout}");
+
+    let (stdout, _, ok) = lb(&dir, &["graph", "--format", "mermaid", "-t", "task"]);
+    assert!(ok);
+    assert!(stdout.starts_with("flowchart LR\n"), "{stdout}");
+    assert!(stdout.contains("unrelated"), "{stdout}");
+    assert!(!stdout.contains("epic"), "{stdout}");
+
+    let (_, stderr, ok) = lb(&dir, &["graph", "--format", "svg"]);
+    assert!(!ok && stderr.contains("unknown graph format"), "{stderr}");
+}
+
+#[test]
+fn plan_shows_waves_and_critical_path() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "epic", "-t", "epic"]);
+    let epic = stdout.split_whitespace().last().unwrap().to_string();
+    let mut ids = Vec::new();
+    for title in ["design", "backend", "frontend", "launch"] {
+        let (stdout, _, _) = lb(&dir, &["create", title, "--parent", &epic]);
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    lb(&dir, &["dep", "add", &ids[0], "--blocks", &ids[1]]);
+    lb(&dir, &["dep", "add", &ids[0], "--blocks", &ids[2]]);
+    lb(&dir, &["dep", "add", &ids[1], "--blocks", &ids[3]]);
+    lb(&dir, &["dep", "add", &ids[2], "--blocks", &ids[3]]);
+
+    let (stdout, stderr, ok) = lb(&dir, &["plan", &epic]);
+    assert!(ok, "{stderr}");
+    assert!(
+        stdout.contains("4 open item(s) in 3 wave(s), up to 2 in parallel"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Wave 2 (2 agent(s)):"), "{stdout}");
+    assert!(stdout.contains("Critical path (3 item(s))"), "{stdout}");
+
+    let (stdout, _, ok) = lb(&dir, &["plan", &epic, "--json"]);
+    assert!(ok);
+    let plan: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(plan["waves"][0][0], ids[0].as_str());
+    assert_eq!(plan["waves"][2][0], ids[3].as_str());
+}
+
+#[test]
+fn why_and_blocked_explain_the_chain() {
+    let dir = setup_git_dir();
+    let mut ids = Vec::new();
+    for title in ["root", "middle", "target"] {
+        let (stdout, _, _) = lb(&dir, &["create", title]);
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    lb(&dir, &["dep", "add", &ids[0], "--blocks", &ids[1]]);
+    lb(&dir, &["dep", "add", &ids[1], "--blocks", &ids[2]]);
+    lb(&dir, &["--as", "bob", "claim", &ids[0]]);
+
+    let (stdout, stderr, ok) = lb(&dir, &["why", &ids[2]]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("is not ready"), "{stdout}");
+    assert!(
+        stdout.contains(&format!("  blocked by {} [open] middle", ids[1])),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!(
+            "    blocked by {} [open, claimed by bob] root",
+            ids[0]
+        )),
+        "{stdout}"
+    );
+    let (stdout, _, _) = lb(&dir, &["why", &ids[0]]);
+    assert!(stdout.contains("claimed by bob"), "{stdout}");
+
+    let (stdout, _, ok) = lb(&dir, &["blocked"]);
+    assert!(ok);
+    let target_line = stdout.lines().find(|l| l.starts_with(&ids[2])).unwrap();
+    assert!(target_line.ends_with(&ids[0]), "{stdout}");
+
+    lb(&dir, &["close", &ids[0]]);
+    lb(&dir, &["close", &ids[1]]);
+    let (stdout, _, _) = lb(&dir, &["why", &ids[2]]);
+    assert!(stdout.contains("is ready"), "{stdout}");
+    let (stdout, _, _) = lb(&dir, &["blocked"]);
+    assert!(stdout.contains("no blocked items"), "{stdout}");
+}
+
+#[test]
+fn unblocks_lists_freed_items_and_ranks_ready() {
+    let dir = setup_git_dir();
+    let mut ids = Vec::new();
+    for title in ["keystone", "next", "after", "loner"] {
+        let (stdout, _, _) = lb(&dir, &["create", title, "-p", "1"]);
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    lb(&dir, &["dep", "add", &ids[0], "--blocks", &ids[1]]);
+    lb(&dir, &["dep", "add", &ids[1], "--blocks", &ids[2]]);
+
+    let (stdout, stderr, ok) = lb(&dir, &["unblocks", &ids[0]]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("unblocks 2 item(s)"), "{stdout}");
+    let direct = stdout.find("directly:").unwrap();
+    let later = stdout.find("once those close:").unwrap();
+    let next = stdout.find(&format!("{} ", ids[1])).unwrap();
+    let after = stdout.find(&format!("{} ", ids[2])).unwrap();
+    assert!(direct < next && next < later && later < after, "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["unblocks", &ids[3]]);
+    assert!(stdout.contains("unblocks nothing"), "{stdout}");
+
+    let (stdout, _, ok) = lb(&dir, &["ready", "--unblocks"]);
+    assert!(ok);
+    assert!(stdout.contains("UNBLOCKS"), "{stdout}");
+    let rows: Vec<&str> = stdout.lines().skip(2).collect();
+    assert!(rows[0].starts_with(&ids[0]), "{stdout}");
+    assert!(rows[1].starts_with(&ids[3]), "{stdout}");
+}
+
+#[test]
+fn ready_ranks_by_configurable_score() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "epic", "-t", "epic", "-p", "0"]);
+    let epic = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "in epic", "-p", "2", "--parent", &epic]);
+    let child = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "loose", "-p", "1"]);
+    let loose = stdout.split_whitespace().last().unwrap().to_string();
+
+    let (stdout, stderr, ok) = lb(&dir, &["ready", "--explain"]);
+    assert!(ok, "{stderr}");
+    assert!(
+        stdout.contains(&format!("parent 8.0 (epic {epic} P0)")),
+        "{stdout}"
+    );
+    let pos = |id: &str| stdout.find(&format!("{id} ")).unwrap();
+    assert!(pos(&loose) < pos(&child), "{stdout}");
+    let (_, stderr, ok) = lb(&dir, &["ready", "--explain", "--unblocks"]);
+    assert!(!ok);
+    assert!(stderr.contains("cannot be used with"), "{stderr}");
+
+    let (_, stderr, ok) = lb(&dir, &["config", "score.parent", "lots"]);
+    assert!(!ok);
+    assert!(stderr.contains("invalid weight"), "{stderr}");
+    lb(&dir, &["config", "score.parent", "5"]);
+    let (stdout, _, _) = lb(&dir, &["config"]);
+    assert!(stdout.contains("score.parent = 5"), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["ready"]);
+    let pos = |id: &str| stdout.find(&format!("{id} ")).unwrap();
+    assert!(pos(&child) < pos(&loose), "{stdout}");
+    assert!(!stdout.contains("score"), "{stdout}");
+}
+
+#[test]
+fn next_claims_the_best_item_and_moves_on_after_a_lost_race() {
+    let (a, b, _bare) = setup_remote_pair();
+    let (stdout, _, _) = lb(&a, &["create", "urgent", "-p", "0"]);
+    let urgent = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&a, &["create", "later", "-p", "3"]);
+    let later = stdout.split_whitespace().last().unwrap().to_string();
+    lb(&a, &["create", "an epic", "-t", "epic", "-p", "4"]);
+    lb(&a, &["sync"]);
+    lb(&b, &["sync"]);
+
+    // a diverges locally so it can't see bob's claim before trying its own
+    lb(&a, &["create", "local only", "-p", "4"]);
+    let (_, stderr, ok) = lb(&b, &["claim", &urgent, "--as", "bob"]);
+    assert!(ok, "bob's claim failed: {stderr}");
+
+    let (stdout, stderr, ok) = lb(&a, &["next", "--as", "alice", "-t", "task"]);
+    assert!(ok, "{stderr}");
+    assert!(
+        stderr.contains(&format!("{urgent} was claimed by bob first")),
+        "{stderr}"
+    );
+    assert!(
+        stdout.contains(&format!("claimed {later} (alice)")),
+        "{stdout}"
+    );
+    assert!(stdout.contains("  Title: later"), "{stdout}");
+
+    lb(&b, &["sync"]);
+    let (stdout, _, _) = lb(&b, &["show", &later]);
+    assert!(stdout.contains("Claimed by: alice"), "{stdout}");
+    let (stdout, _, _) = lb(&b, &["show", &urgent]);
+    assert!(stdout.contains("Claimed by: bob"), "{stdout}");
+
+    let (stdout, _, ok) = lb(&b, &["next", "--parent", &urgent]);
+    assert!(ok);
+    assert!(stdout.contains("no ready items"), "{stdout}");
+}
+
+#[test]
+fn progress_rolls_up_under_parents() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "epic", "-t", "epic"]);
+    let epic = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(
+        &dir,
+        &["create", "feature", "-t", "feature", "--parent", &epic],
+    );
+    let feature = stdout.split_whitespace().last().unwrap().to_string();
+    let mut tasks = Vec::new();
+    for title in ["done", "taken", "waiting"] {
+        let (stdout, _, _) = lb(&dir, &["create", title, "--parent", &feature]);
+        tasks.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    lb(&dir, &["close", &tasks[0]]);
+    lb(&dir, &["claim", &tasks[1], "--as", "alice"]);
+    lb(&dir, &["dep", "add", &tasks[1], "--blocks", &tasks[2]]);
+
+    let (stdout, _, _) = lb(&dir, &["show", &epic]);
+    assert!(
+        stdout.contains("  Progress: 1/4 closed (25%), 1 claimed, 1 blocked"),
+        "{stdout}"
+    );
+    let (stdout, _, _) = lb(&dir, &["list", "--tree"]);
+    let line = stdout.lines().find(|l| l.contains(&feature)).unwrap();
+    assert!(
+        line.ends_with("— 1/3 closed (33%), 1 claimed, 1 blocked"),
+        "{stdout}"
+    );
+
+    let (stdout, stderr, ok) = lb(&dir, &["progress", &epic]);
+    assert!(ok, "{stderr}");
+    let row = stdout.lines().find(|l| l.starts_with(&feature)).unwrap();
+    let cols: Vec<&str> = row.split_whitespace().collect();
+    assert_eq!(cols[1..5], ["open", "1/4", "1", "1"], "{stdout}");
+    let (stdout, _, _) = lb(&dir, &["progress", &tasks[0]]);
+    assert!(stdout.contains("has no children"), "{stdout}");
+}
+
+#[test]
+fn estimates_sum_up_and_weigh_plans() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "epic", "-t", "epic"]);
+    let epic = stdout.split_whitespace().last().unwrap().to_string();
+    let mut ids = Vec::new();
+    for (title, est) in [("small", "1"), ("big", "5pt"), ("other", "1pt")] {
+        let (stdout, stderr, ok) = lb(&dir, &["create", title, "-e", est, "--parent", &epic]);
+        assert!(ok, "{stderr}");
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    lb(&dir, &["dep", "add", &ids[0], "--blocks", &ids[1]]);
+
+    let (stdout, _, _) = lb(&dir, &["show", &epic]);
+    assert!(stdout.contains("  Estimate: 7pt (descendants)"), "{stdout}");
+    let (stdout, _, _) = lb(&dir, &["show", &ids[1]]);
+    assert!(stdout.contains("  Estimate: 5pt\n"), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["plan", &epic]);
+    assert!(
+        stdout.contains(&format!(
+            "Critical path (2 item(s), weight 6): {} -> {}",
+            ids[0], ids[1]
+        )),
+        "{stdout}"
+    );
+
+    lb(&dir, &["close", &ids[1]]);
+    let (stdout, _, _) = lb(&dir, &["show", &epic]);
+    assert!(
+        stdout.contains("; 5pt/7pt estimated done (71%)"),
+        "{stdout}"
+    );
+
+    let (_, stderr, ok) = lb(&dir, &["update", &ids[2], "-e", "4h"]);
+    assert!(ok, "{stderr}");
+    let (stdout, _, _) = lb(&dir, &["show", &epic]);
+    assert!(stdout.contains("6pt + 4h (descendants)"), "{stdout}");
+    lb(&dir, &["update", &ids[2], "-e", ""]);
+    let (stdout, _, _) = lb(&dir, &["show", &ids[2]]);
+    assert!(!stdout.contains("Estimate"), "{stdout}");
+    let (_, stderr, ok) = lb(&dir, &["update", &ids[2], "-e", "lots"]);
+    assert!(!ok);
+    assert!(stderr.contains("invalid estimate"), "{stderr}");
+}
+
+#[test]
+fn due_dates_filter_inherit_and_show_in_prime() {
+    let dir = setup_git_dir();
+    let (stdout, _, _) = lb(&dir, &["create", "launch", "-t", "epic", "--due", "+5d"]);
+    let epic = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, _, _) = lb(&dir, &["create", "docs", "--parent", &epic]);
+    let child = stdout.split_whitespace().last().unwrap().to_string();
+    let (stdout, stderr, ok) = lb(&dir, &["create", "late", "--due", "2020-01-01"]);
+    assert!(ok, "{stderr}");
+    let late = stdout.split_whitespace().last().unwrap().to_string();
+
+    let (stdout, _, _) = lb(&dir, &["show", &child]);
+    assert!(
+        stdout.contains(&format!("(from {epic}, in 5d)")),
+        "{stdout}"
+    );
+    let (stdout, _, _) = lb(&dir, &["show", &late]);
+    assert!(stdout.contains("  Due: 2020-01-01 ("), "{stdout}");
+    assert!(stdout.contains("d overdue)"), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["list"]);
+    let row = stdout.lines().find(|l| l.starts_with(&child)).unwrap();
+    assert!(row.contains(" ("), "{stdout}");
+    assert!(stdout.contains("2020-01-01"), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["list", "--overdue"]);
+    assert!(stdout.contains(&late), "{stdout}");
+    assert!(!stdout.contains(&epic), "{stdout}");
+    let (stdout, _, _) = lb(&dir, &["list", "--due-before", "+30d"]);
+    assert!(stdout.contains(&late) && stdout.contains(&epic), "{stdout}");
+    assert!(!stdout.contains(&child), "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["prime"]);
+    let overdue = stdout.find("## Overdue").expect("overdue section");
+    assert!(stdout[overdue..].contains(&late), "{stdout}");
+
+    let (_, stderr, ok) = lb(&dir, &["update", &late, "--due", "someday"]);
+    assert!(!ok);
+    assert!(stderr.contains("invalid due date"), "{stderr}");
+    lb(&dir, &["update", &late, "--due", ""]);
+    let (stdout, _, _) = lb(&dir, &["list", "--overdue"]);
+    assert!(!stdout.contains(&late), "{stdout}");
+}
+
+#[test]
+fn milestones_schedule_filter_and_report() {
+    let dir = setup_git_dir();
+    let (stdout, stderr, ok) = lb(
+        &dir,
+        &["milestone", "create", "v1", "--target", "2030-01-15"],
+    );
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("created milestone v1"), "{stdout}");
+    let (_, stderr, ok) = lb(&dir, &["milestone", "create", "v1"]);
+    assert!(!ok);
+    assert!(stderr.contains("already exists"), "{stderr}");
+
+    let mut ids = Vec::new();
+    for (title, milestone) in [("ship", "v1"), ("polish", "v1"), ("outside", "")] {
+        let mut args = vec!["create", title];
+        if !milestone.is_empty() {
+            args.extend(["--milestone", milestone]);
+        }
+        let (stdout, stderr, ok) = lb(&dir, &args);
+        assert!(ok, "{stderr}");
+        ids.push(stdout.split_whitespace().last().unwrap().to_string());
+    }
+    lb(&dir, &["dep", "add", &ids[2], "--blocks", &ids[0]]);
+    lb(&dir, &["close", &ids[1]]);
+    let (_, stderr, ok) = lb(&dir, &["update", &ids[2], "--milestone", "v9"]);
+    assert!(!ok);
+    assert!(stderr.contains("no milestone named 'v9'"), "{stderr}");
+
+    let (stdout, _, _) = lb(&dir, &["milestone", "list"]);
+    let row = stdout.lines().find(|l| l.starts_with("v1 ")).unwrap();
+    let cols: Vec<&str> = row.split_whitespace().collect();
+    assert_eq!(cols, ["v1", "open", "2030-01-15", "1", "1"], "{stdout}");
+
+    let (stdout, _, _) = lb(&dir, &["milestone", "show", "v1"]);
+    assert!(stdout.contains("  Items: 1 open, 1 closed"), "{stdout}");
+    assert!(stdout.contains("Blocked by work outside v1:"), "{stdout}");
+    assert!(
+        stdout.contains(&format!(
+            "    blocked by {} [open, no milestone] outside",
+            ids[2]
+        )),
+        "{stdout}"
+    );
+
+    let (stdout, _, _) = lb(&dir, &["list", "--all", "--milestone", "v1"]);
+    assert!(
+        stdout.contains(&ids[0]) && stdout.contains(&ids[1]),
+        "{stdout}"
+    );
+    assert!(!stdout.contains(&ids[2]), "{stdout}");
+    let (_, stderr, ok) = lb(&dir, &["list", "--milestone", "v9"]);
+    assert!(!ok);
+    assert!(stderr.contains("no milestone named 'v9'"), "{stderr}");
+    let (stdout, _, _) = lb(&dir, &["ready", "--milestone", "v1"]);
+    assert!(stdout.contains("no ready items"), "{stdout}");
+    let (stdout, _, _) = lb(&dir, &["show", &ids[0]]);
+    assert!(
+        stdout.contains("  Milestone: v1 (open, target 2030-01-15)"),
+        "{stdout}"
+    );
+
+    // Milestones live in meta.json under the per-item layout
+    lb(&dir, &["config", "layout", "per-item"]);
+    let (stdout, stderr, ok) = lb(&dir, &["milestone", "close", "v1"]);
+    assert!(ok, "{stderr}");
+    assert!(stdout.contains("1 item(s) still open"), "{stdout}");
+    let meta = git(&dir, &["show", "litebrite:meta.json"]);
+    assert!(meta.contains("\"milestones\""), "{meta}");
+    let (stdout, _, _) = lb(&dir, &["milestone", "list"]);
+    assert!(stdout.contains("no milestones"), "{stdout}");
+    let (stdout, _, _) = lb(&dir, &["milestone", "list", "--all"]);
+    assert!(stdout.contains("closed"), "{stdout}");
+}